@@ -1,55 +1,224 @@
-use super::constants::*;
 use super::error::*;
-use serde::Deserialize;
+use super::horizon::HorizonClient;
+use super::strkey;
+use ed25519_dalek::Verifier;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fs::File;
 pub use substrate_stellar_sdk::horizon::json_response_types::{AccountResponse, Signer};
 pub use substrate_stellar_sdk::types::SignatureHint;
-use substrate_stellar_sdk::{IntoAccountId, IntoPublicKey, PublicKey, StellarSdkError};
+use substrate_stellar_sdk::{
+    types::{DecoratedSignature, SignerKey},
+    IntoAccountId, PublicKey, StellarSdkError,
+};
 use thiserror::Error;
 
-pub fn get_account<T: IntoAccountId>(acc_id: T) -> Result<AccountResponse> {
-    Ok(horizon_mainnet().fetch_account(acc_id, FETCH_TIMEOUT)?)
+pub fn get_account<T: IntoAccountId>(
+    horizon: &dyn HorizonClient,
+    acc_id: T,
+) -> Result<AccountResponse> {
+    horizon.fetch_account(&acc_id.into_account_id()?)
 }
 
-pub fn get_mtl_foundation() -> Result<AccountResponse> {
-    get_account(MTL_FOUNDATION)
+/// Signer of an account, Horizon lists all of these kinds, see
+/// https://developers.stellar.org/docs/glossary/multisig/#additional-signing-keys
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MtlSignerKey {
+    Ed25519(PublicKey),
+    /// Hash of the transaction the signer authorizes
+    PreAuthTx([u8; 32]),
+    /// SHA256 hash of the preimage that serves as the signature
+    HashX([u8; 32]),
+    /// Ed25519 key that signs the payload instead of the transaction
+    SignedPayload(PublicKey, Vec<u8>),
 }
 
-pub fn get_mtlcity_foundation() -> Result<AccountResponse> {
-    get_account(MTLCITY_ISSUERER)
+/// Keys are stored in their strkey encoding
+impl Serialize for MtlSignerKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_encoding())
+    }
 }
 
-pub fn get_btc_treasury() -> Result<AccountResponse> {
-    get_account(BTC_TREASURY)
+impl<'de> Deserialize<'de> for MtlSignerKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        MtlSignerKey::from_encoding(&key).map_err(serde::de::Error::custom)
+    }
 }
 
-pub fn get_mtl_additional() -> Result<AccountResponse> {
-    get_account(MTL_ADDITIONAL_ACCOUNT)
+impl MtlSignerKey {
+    pub fn from_encoding(key: &str) -> Result<Self> {
+        let (version, payload) = strkey::decode(key).ok_or(MtlError::UnsupportedSignerKey)?;
+        if version == strkey::VERSION_ACCOUNT_ID {
+            Ok(MtlSignerKey::Ed25519(PublicKey::PublicKeyTypeEd25519(
+                uint256(&payload)?,
+            )))
+        } else if version == strkey::VERSION_PRE_AUTH_TX {
+            Ok(MtlSignerKey::PreAuthTx(uint256(&payload)?))
+        } else if version == strkey::VERSION_SHA256_HASH {
+            Ok(MtlSignerKey::HashX(uint256(&payload)?))
+        } else if version == strkey::VERSION_SIGNED_PAYLOAD && payload.len() >= 36 {
+            let mut len = [0; 4];
+            len.copy_from_slice(&payload[32..36]);
+            let len = u32::from_be_bytes(len) as usize;
+            if len > 64 || payload.len() != 36 + len + padding(len) {
+                return Err(MtlError::UnsupportedSignerKey);
+            }
+            Ok(MtlSignerKey::SignedPayload(
+                PublicKey::PublicKeyTypeEd25519(uint256(&payload[..32])?),
+                payload[36..36 + len].to_vec(),
+            ))
+        } else {
+            Err(MtlError::UnsupportedSignerKey)
+        }
+    }
+
+    pub fn to_encoding(&self) -> String {
+        match self {
+            MtlSignerKey::Ed25519(pk) => strkey::encode(strkey::VERSION_ACCOUNT_ID, pk.as_binary()),
+            MtlSignerKey::PreAuthTx(h) => strkey::encode(strkey::VERSION_PRE_AUTH_TX, h),
+            MtlSignerKey::HashX(h) => strkey::encode(strkey::VERSION_SHA256_HASH, h),
+            MtlSignerKey::SignedPayload(pk, payload) => {
+                let mut raw = pk.as_binary().to_vec();
+                raw.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+                raw.extend_from_slice(payload);
+                raw.resize(raw.len() + padding(payload.len()), 0);
+                strkey::encode(strkey::VERSION_SIGNED_PAYLOAD, &raw)
+            }
+        }
+    }
+
+    /// Human readable kind of the key
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MtlSignerKey::Ed25519(_) => "ed25519",
+            MtlSignerKey::PreAuthTx(_) => "pre-auth tx",
+            MtlSignerKey::HashX(_) => "hash-x",
+            MtlSignerKey::SignedPayload(..) => "signed payload",
+        }
+    }
+
+    pub fn public_key(&self) -> Option<&PublicKey> {
+        match self {
+            MtlSignerKey::Ed25519(pk) => Some(pk),
+            _ => None,
+        }
+    }
+
+    /// Hint of the decorated signature that satisfies the signer, pre-auth
+    /// signers don't need one
+    pub fn signature_hint(&self) -> Option<SignatureHint> {
+        match self {
+            MtlSignerKey::Ed25519(pk) => Some(pk.get_signature_hint()),
+            MtlSignerKey::PreAuthTx(_) => None,
+            MtlSignerKey::HashX(h) => Some([h[28], h[29], h[30], h[31]]),
+            MtlSignerKey::SignedPayload(pk, payload) => {
+                let mut tail = [0; 4];
+                let start = payload.len().saturating_sub(4);
+                tail[..payload.len() - start].copy_from_slice(&payload[start..]);
+                let mut hint = pk.get_signature_hint();
+                for (h, t) in hint.iter_mut().zip(tail.iter()) {
+                    *h ^= t;
+                }
+                Some(hint)
+            }
+        }
+    }
+
+    /// Whether the decorated signature satisfies the signer for the
+    /// transaction with the given hash. Pre-auth signers don't need
+    /// signatures and never match.
+    pub fn verify(&self, tx_hash: &[u8], signature: &DecoratedSignature) -> bool {
+        if Some(signature.hint) != self.signature_hint() {
+            return false;
+        }
+        let signature = signature.signature.get_vec();
+        match self {
+            MtlSignerKey::Ed25519(pk) => verify_ed25519(pk, tx_hash, signature),
+            MtlSignerKey::PreAuthTx(_) => false,
+            MtlSignerKey::HashX(h) => Sha256::digest(signature)[..] == h[..],
+            MtlSignerKey::SignedPayload(pk, payload) => verify_ed25519(pk, payload, signature),
+        }
+    }
 }
 
-pub fn get_btc_foundation() -> Result<AccountResponse> {
-    get_account(BTC_FOUNDATION)
+fn verify_ed25519(pk: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    let key = match ed25519_dalek::PublicKey::from_bytes(pk.as_binary()) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => key.verify(message, &signature).is_ok(),
+        Err(_) => false,
+    }
 }
 
-pub fn get_rect_foundation() -> Result<AccountResponse> {
-    get_account(MTL_RECT_ACCOUNT)
+/// Decode detached ed25519 signature given either in hex or in base64
+pub fn decode_signature(encoded: &str) -> Result<Vec<u8>> {
+    let encoded = encoded.trim();
+    let signature = match hex::decode(encoded) {
+        Ok(signature) => signature,
+        Err(_) => base64::decode(encoded).map_err(|_| MtlError::MalformedSignature)?,
+    };
+    if signature.len() != 64 {
+        return Err(MtlError::SignatureLength(signature.len()));
+    }
+    Ok(signature)
 }
 
-pub fn get_mtl_signers(account: &AccountResponse) -> Result<Vec<(PublicKey, i32)>> {
+impl std::fmt::Display for MtlSignerKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_encoding())
+    }
+}
+
+impl From<&SignerKey> for MtlSignerKey {
+    fn from(key: &SignerKey) -> Self {
+        match key {
+            SignerKey::SignerKeyTypeEd25519(k) => {
+                MtlSignerKey::Ed25519(PublicKey::PublicKeyTypeEd25519(*k))
+            }
+            SignerKey::SignerKeyTypePreAuthTx(h) => MtlSignerKey::PreAuthTx(*h),
+            SignerKey::SignerKeyTypeHashX(h) => MtlSignerKey::HashX(*h),
+        }
+    }
+}
+
+/// Zero bytes that align XDR opaque of the length to 4 bytes
+fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+fn uint256(bytes: &[u8]) -> Result<[u8; 32]> {
+    let mut res = [0; 32];
+    if bytes.len() != res.len() {
+        return Err(MtlError::UnsupportedSignerKey);
+    }
+    res.copy_from_slice(bytes);
+    Ok(res)
+}
+
+/// All signers of the account including the master key
+pub fn get_account_signers(account: &AccountResponse) -> Result<Vec<(MtlSignerKey, i32)>> {
     let mut keys = Vec::new();
     for sk in account.signers.iter() {
-        keys.push((signer_key(&sk)?, sk.weight));
+        keys.push((MtlSignerKey::from_encoding(&sk.key)?, sk.weight));
     }
     Ok(keys)
 }
 
-fn signer_key(sk: &Signer) -> Result<PublicKey> {
-    Ok(sk.key.as_bytes().into_public_key()?)
-}
-
-pub fn get_required_weight(account: &AccountResponse) -> u8 {
-    account.thresholds.high_threshold
+/// Ed25519 signers of the account, other kinds of keys are skipped
+pub fn get_mtl_signers(account: &AccountResponse) -> Result<Vec<(PublicKey, i32)>> {
+    Ok(get_account_signers(account)?
+        .into_iter()
+        .filter_map(|(key, w)| match key {
+            MtlSignerKey::Ed25519(pk) => Some((pk, w)),
+            _ => None,
+        })
+        .collect())
 }
 
 #[derive(Deserialize)]
@@ -88,3 +257,31 @@ pub fn get_telegram_mapping(file_name: &str) -> std::result::Result<UsersMapping
     }
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_hex_and_base64_signatures() {
+        let raw = [7; 64];
+        let signature = decode_signature(&hex::encode(&raw[..])).unwrap();
+        assert_eq!(signature, raw.to_vec());
+        assert_eq!(
+            decode_signature(&format!(" {} ", base64::encode(&raw[..]))).unwrap(),
+            signature
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        assert!(matches!(
+            decode_signature("abcd"),
+            Err(MtlError::SignatureLength(2))
+        ));
+        assert!(matches!(
+            decode_signature("not a signature"),
+            Err(MtlError::MalformedSignature)
+        ));
+    }
+}
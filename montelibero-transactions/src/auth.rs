@@ -0,0 +1,289 @@
+//! Stellar web authentication, see
+//! https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0010.md
+
+use super::account::MtlSignerKey;
+use super::error::*;
+use super::network::NetworkConfig;
+use super::strkey;
+use super::transaction::MtlTransaction;
+use ed25519_dalek::{Keypair, SecretKey, Signer as _};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use substrate_stellar_sdk::{
+    compound_types::{LimitedString, LimitedVarArray, LimitedVarOpaque},
+    types::{
+        DecoratedSignature, ManageDataOp, Memo, Operation, OperationBody, TimeBounds,
+        TransactionExt, TransactionV1Envelope,
+    },
+    MuxedAccount, PublicKey, Transaction,
+};
+
+/// How long the client has to sign the challenge, in seconds
+pub const CHALLENGE_TIMEOUT: u64 = 300;
+
+/// Issues and verifies challenge transactions on behalf of the service
+pub struct WebAuth {
+    keypair: Keypair,
+    home_domain: String,
+    web_auth_domain: String,
+    /// Txids of accepted challenges with their max time, so a signed
+    /// challenge logs in only once
+    used: Mutex<HashMap<Vec<u8>, u64>>,
+}
+
+impl WebAuth {
+    /// `secret` is the `S...` seed of the key the challenges are signed with
+    pub fn new(secret: &str, home_domain: String, web_auth_domain: String) -> Result<Self> {
+        let seed = match strkey::decode(secret) {
+            Some((version, seed)) if version == strkey::VERSION_SEED => seed,
+            _ => return Err(MtlError::InvalidSecretKey),
+        };
+        let secret = SecretKey::from_bytes(&seed).map_err(|_| MtlError::InvalidSecretKey)?;
+        let public = (&secret).into();
+        Ok(WebAuth {
+            keypair: Keypair { secret, public },
+            home_domain,
+            web_auth_domain,
+            used: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn server_key(&self) -> PublicKey {
+        PublicKey::from_binary(self.keypair.public.to_bytes())
+    }
+
+    fn auth_data_name(&self) -> String {
+        format!("{} auth", self.home_domain)
+    }
+
+    /// Challenge for the client to sign, `nonce` must be random
+    pub fn challenge(
+        &self,
+        network: &NetworkConfig,
+        client: &PublicKey,
+        nonce: &[u8; 48],
+        now: u64,
+    ) -> Result<MtlTransaction> {
+        let server = MuxedAccount::KeyTypeEd25519(self.keypair.public.to_bytes());
+        let operations = vec![
+            manage_data(
+                MuxedAccount::KeyTypeEd25519(*client.as_binary()),
+                &self.auth_data_name(),
+                base64::encode(&nonce[..]).into_bytes(),
+            )?,
+            manage_data(
+                server.clone(),
+                "web_auth_domain",
+                self.web_auth_domain.clone().into_bytes(),
+            )?,
+        ];
+        let tx = Transaction {
+            source_account: server,
+            fee: 100 * operations.len() as u32,
+            seq_num: 0,
+            time_bounds: Some(TimeBounds {
+                min_time: now,
+                max_time: now + CHALLENGE_TIMEOUT,
+            }),
+            memo: Memo::MemoNone,
+            operations: LimitedVarArray::new(operations)
+                .map_err(|_| challenge_error("too many operations"))?,
+            ext: TransactionExt::V0,
+        };
+        let unsigned = MtlTransaction::Tx(TransactionV1Envelope {
+            tx,
+            signatures: LimitedVarArray::new(vec![]).unwrap(),
+        });
+        let signature = self.keypair.sign(&unsigned.txid(network)).to_bytes();
+        unsigned.with_signatures(vec![DecoratedSignature {
+            hint: self.server_key().get_signature_hint(),
+            signature: LimitedVarOpaque::new(signature.to_vec()).unwrap(),
+        }])
+    }
+
+    /// Check the challenge signed by the client, returns the client key.
+    /// Each challenge is accepted once.
+    pub fn verify(
+        &self,
+        network: &NetworkConfig,
+        challenge: &MtlTransaction,
+        now: u64,
+    ) -> Result<PublicKey> {
+        let tx = match challenge {
+            MtlTransaction::Tx(envelope) => &envelope.tx,
+            MtlTransaction::FeeBump(..) => return Err(challenge_error("fee bump envelope")),
+        };
+        let server = self.server_key();
+        if challenge.source_account()? != server {
+            return Err(challenge_error("source account is not the server"));
+        }
+        if tx.seq_num != 0 {
+            return Err(challenge_error("sequence number is not zero"));
+        }
+        let expires = match &tx.time_bounds {
+            Some(bounds) if bounds.min_time <= now && now <= bounds.max_time => bounds.max_time,
+            _ => return Err(challenge_error("challenge has expired")),
+        };
+
+        let mut client = None;
+        for (i, op) in tx.operations.get_vec().iter().enumerate() {
+            let data = match &op.body {
+                OperationBody::ManageData(data) => data,
+                _ => return Err(challenge_error("operation is not manage data")),
+            };
+            let source = match &op.source_account {
+                Some(MuxedAccount::KeyTypeEd25519(k)) => PublicKey::from_binary(*k),
+                _ => return Err(challenge_error("operation has no source account")),
+            };
+            let name = data.data_name.get_vec();
+            let value = data.data_value.as_ref().map(|v| v.get_vec().as_slice());
+            if i == 0 {
+                if name[..] != *self.auth_data_name().as_bytes() {
+                    return Err(challenge_error("wrong home domain"));
+                }
+                if value.map(|v| v.len()) != Some(64) {
+                    return Err(challenge_error("malformed nonce"));
+                }
+                client = Some(source);
+            } else if source != server {
+                return Err(challenge_error("operation source is not the server"));
+            } else if name[..] == b"web_auth_domain"[..]
+                && value != Some(self.web_auth_domain.as_bytes())
+            {
+                return Err(challenge_error("wrong web auth domain"));
+            }
+        }
+        let client = client.ok_or_else(|| challenge_error("no operations"))?;
+
+        let txid = challenge.txid(network);
+        let server_key = MtlSignerKey::Ed25519(server);
+        let client_key = MtlSignerKey::Ed25519(client.clone());
+        let signatures = challenge.decorated_signatures();
+        if !signatures.iter().any(|s| server_key.verify(&txid, s)) {
+            return Err(challenge_error("no server signature"));
+        }
+        if !signatures.iter().any(|s| client_key.verify(&txid, s)) {
+            return Err(challenge_error("no client signature"));
+        }
+        if signatures.len() != 2 {
+            return Err(challenge_error("unexpected signatures"));
+        }
+
+        let mut used = self.used.lock().unwrap();
+        used.retain(|_, max_time| now <= *max_time);
+        if used.insert(txid, expires).is_some() {
+            return Err(challenge_error("challenge was already used"));
+        }
+        Ok(client)
+    }
+}
+
+fn manage_data(source: MuxedAccount, name: &str, value: Vec<u8>) -> Result<Operation> {
+    Ok(Operation {
+        source_account: Some(source),
+        body: OperationBody::ManageData(ManageDataOp {
+            data_name: LimitedString::new(name.as_bytes().to_vec())
+                .map_err(|_| challenge_error("home domain is too long"))?,
+            data_value: Some(
+                LimitedVarOpaque::new(value)
+                    .map_err(|_| challenge_error("web auth domain is too long"))?,
+            ),
+        }),
+    })
+}
+
+fn challenge_error(reason: &str) -> MtlError {
+    MtlError::InvalidChallenge(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use substrate_stellar_sdk::types::SignatureHint;
+
+    const NOW: u64 = 1_650_000_000;
+
+    fn web_auth() -> WebAuth {
+        let secret = strkey::encode(strkey::VERSION_SEED, &[9; 32]);
+        WebAuth::new(
+            &secret,
+            "multisig.montelibero.org".to_owned(),
+            "multisig.montelibero.org".to_owned(),
+        )
+        .unwrap()
+    }
+
+    fn client() -> (Keypair, PublicKey) {
+        let secret = SecretKey::from_bytes(&[1; 32]).unwrap();
+        let public: ed25519_dalek::PublicKey = (&secret).into();
+        let key = PublicKey::from_binary(public.to_bytes());
+        (Keypair { secret, public }, key)
+    }
+
+    fn sign(tx: &MtlTransaction, keypair: &Keypair, hint: SignatureHint) -> MtlTransaction {
+        let network = NetworkConfig::default();
+        let mut signatures = tx.decorated_signatures().to_vec();
+        signatures.push(DecoratedSignature {
+            hint,
+            signature: LimitedVarOpaque::new(keypair.sign(&tx.txid(&network)).to_bytes().to_vec())
+                .unwrap(),
+        });
+        tx.with_signatures(signatures).unwrap()
+    }
+
+    #[test]
+    fn signed_challenge_identifies_client() {
+        let auth = web_auth();
+        let network = NetworkConfig::default();
+        let (keypair, key) = client();
+        let challenge = auth.challenge(&network, &key, &[7; 48], NOW).unwrap();
+        assert!(matches!(
+            auth.verify(&network, &challenge, NOW + 10),
+            Err(MtlError::InvalidChallenge(_))
+        ));
+
+        let signed = sign(&challenge, &keypair, key.get_signature_hint());
+        let encoded = MtlTransaction::from_encoding(&signed.into_encoding()).unwrap();
+        assert!(auth
+            .verify(&network, &encoded, NOW + CHALLENGE_TIMEOUT + 1)
+            .is_err());
+        assert_eq!(auth.verify(&network, &encoded, NOW + 10).unwrap(), key);
+    }
+
+    #[test]
+    fn challenge_is_accepted_once() {
+        let auth = web_auth();
+        let network = NetworkConfig::default();
+        let (keypair, key) = client();
+        let challenge = auth.challenge(&network, &key, &[7; 48], NOW).unwrap();
+        let signed = sign(&challenge, &keypair, key.get_signature_hint());
+        assert_eq!(auth.verify(&network, &signed, NOW + 10).unwrap(), key);
+        assert!(matches!(
+            auth.verify(&network, &signed, NOW + 20),
+            Err(MtlError::InvalidChallenge(_))
+        ));
+
+        let other = auth.challenge(&network, &key, &[6; 48], NOW + 30).unwrap();
+        let signed = sign(&other, &keypair, key.get_signature_hint());
+        assert_eq!(auth.verify(&network, &signed, NOW + 40).unwrap(), key);
+        assert_eq!(auth.used.lock().unwrap().len(), 2);
+        assert!(auth.verify(&network, &signed, NOW + 50).is_err());
+    }
+
+    #[test]
+    fn challenge_of_other_server_is_rejected() {
+        let auth = web_auth();
+        let network = NetworkConfig::default();
+        let (keypair, key) = client();
+        let other = WebAuth::new(
+            &strkey::encode(strkey::VERSION_SEED, &[8; 32]),
+            "multisig.montelibero.org".to_owned(),
+            "multisig.montelibero.org".to_owned(),
+        )
+        .unwrap();
+        let challenge = other.challenge(&network, &key, &[7; 48], NOW).unwrap();
+        let signed = sign(&challenge, &keypair, key.get_signature_hint());
+        assert!(auth.verify(&network, &signed, NOW).is_err());
+        assert!(WebAuth::new("SBAD", String::new(), String::new()).is_err());
+    }
+}
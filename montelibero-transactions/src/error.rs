@@ -26,8 +26,80 @@ pub enum MtlError {
     UpdateSignatureRemoved,
     #[error("Transaction has too much signatures")]
     SignaturesExcess,
+    #[error("Transaction can't carry more than 20 signatures")]
+    TooManySignatures,
+    #[error("Transaction has signature with hint {0} that doesn't verify against any signer")]
+    InvalidSignature(String),
+    #[error("Signature is neither hex nor base64 encoded")]
+    MalformedSignature,
+    #[error("Signature must be 64 bytes long, got {0}")]
+    SignatureLength(usize),
+    #[error("Weight or threshold {0} doesn't fit into 0-255")]
+    WeightOverflow(u32),
+    #[error("Transaction would lock out account {0}: {1}")]
+    LockOut(String, String),
+    #[error("Transaction doesn't have enough signatures to be submitted")]
+    NotEnoughSignatures,
+    #[error("Transaction can't change status from {0} to {1}")]
+    StatusTransition(crate::status::TxStatus, crate::status::TxStatus),
+    #[error("Invalid secret key")]
+    InvalidSecretKey,
+    #[error("Invalid authentication challenge: {0}")]
+    InvalidChallenge(String),
+    #[error("Unknown transaction status {0}")]
+    UnknownStatus(String),
+    #[error("Horizon returned unexpected response on transaction submission")]
+    UnexpectedSubmitResponse,
     #[error("Failed to request from Horizon server: {0}")]
     FetchError(#[from] substrate_stellar_sdk::horizon::FetchError),
+    #[error("Account is not found")]
+    AccountNotFound,
+    #[error("Failed to decode Horizon response: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 pub type Result<T> = std::result::Result<T, MtlError>;
+
+impl MtlError {
+    /// Stable machine readable name of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            MtlError::Decode(_) => "decode",
+            MtlError::Sdk(_) => "sdk",
+            MtlError::WrongSourceAccount => "wrong_source_account",
+            MtlError::DeprecatedTxVersion => "deprecated_tx_version",
+            MtlError::UnsupportedTx => "unsupported_tx",
+            MtlError::UnsupportedSignerKey => "unsupported_signer_key",
+            MtlError::NonStandardFee => "non_standard_fee",
+            MtlError::SequenceNumber => "sequence_number",
+            MtlError::TooLittleTimeBound => "too_little_time_bound",
+            MtlError::UpdateContentChanged => "update_content_changed",
+            MtlError::UpdateSignatureRemoved => "update_signature_removed",
+            MtlError::SignaturesExcess => "signatures_excess",
+            MtlError::TooManySignatures => "too_many_signatures",
+            MtlError::InvalidSignature(_) => "invalid_signature",
+            MtlError::MalformedSignature => "malformed_signature",
+            MtlError::SignatureLength(_) => "signature_length",
+            MtlError::WeightOverflow(_) => "weight_overflow",
+            MtlError::LockOut(_, _) => "lock_out",
+            MtlError::NotEnoughSignatures => "not_enough_signatures",
+            MtlError::StatusTransition(_, _) => "status_transition",
+            MtlError::InvalidSecretKey => "invalid_secret_key",
+            MtlError::InvalidChallenge(_) => "invalid_challenge",
+            MtlError::UnknownStatus(_) => "unknown_status",
+            MtlError::UnexpectedSubmitResponse => "unexpected_submit_response",
+            MtlError::FetchError(_) => "horizon_fetch",
+            MtlError::AccountNotFound => "account_not_found",
+            MtlError::Json(_) => "horizon_json",
+        }
+    }
+
+    /// Whether the error comes from talking to Horizon rather than from the
+    /// transaction itself
+    pub fn is_horizon_failure(&self) -> bool {
+        matches!(
+            self,
+            MtlError::FetchError(_) | MtlError::Json(_) | MtlError::UnexpectedSubmitResponse
+        )
+    }
+}
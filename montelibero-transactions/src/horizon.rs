@@ -0,0 +1,180 @@
+use super::constants::*;
+use super::error::*;
+use super::network::NetworkConfig;
+use super::submit::SubmitResult;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use substrate_stellar_sdk::horizon::{json_response_types::AccountResponse, FetchError};
+use substrate_stellar_sdk::{AccountId, TransactionEnvelope, XdrCodec};
+
+/// Ledger queries the validation needs. The production implementation talks
+/// to a Horizon server, tests and rehearsals can use `MemoryHorizon`.
+pub trait HorizonClient: Send + Sync {
+    /// Network the client is connected to
+    fn network(&self) -> &NetworkConfig;
+
+    fn fetch_account(&self, account: &AccountId) -> Result<AccountResponse>;
+
+    fn fetch_next_sequence_number(&self, account: &AccountId) -> Result<i64>;
+
+    /// Returns whether transaction with given hash was applied successfully
+    fn query_transaction(&self, txid: &[u8]) -> Result<bool>;
+
+    /// Returns the ledger result, rejected transactions are not an error
+    fn submit_transaction(&self, envelope: TransactionEnvelope) -> Result<SubmitResult>;
+}
+
+/// Shared clients, so the blocking calls can be moved to another thread
+impl<T: HorizonClient + ?Sized> HorizonClient for Arc<T> {
+    fn network(&self) -> &NetworkConfig {
+        (**self).network()
+    }
+
+    fn fetch_account(&self, account: &AccountId) -> Result<AccountResponse> {
+        (**self).fetch_account(account)
+    }
+
+    fn fetch_next_sequence_number(&self, account: &AccountId) -> Result<i64> {
+        (**self).fetch_next_sequence_number(account)
+    }
+
+    fn query_transaction(&self, txid: &[u8]) -> Result<bool> {
+        (**self).query_transaction(txid)
+    }
+
+    fn submit_transaction(&self, envelope: TransactionEnvelope) -> Result<SubmitResult> {
+        (**self).submit_transaction(envelope)
+    }
+}
+
+pub struct HorizonServer {
+    config: NetworkConfig,
+}
+
+impl HorizonServer {
+    pub fn new(config: NetworkConfig) -> Self {
+        HorizonServer { config }
+    }
+}
+
+impl HorizonClient for HorizonServer {
+    fn network(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    fn fetch_account(&self, account: &AccountId) -> Result<AccountResponse> {
+        Ok(self
+            .config
+            .horizon()
+            .fetch_account(account.clone(), FETCH_TIMEOUT)?)
+    }
+
+    fn fetch_next_sequence_number(&self, account: &AccountId) -> Result<i64> {
+        Ok(self
+            .config
+            .horizon()
+            .fetch_next_sequence_number(account.clone(), FETCH_TIMEOUT)?)
+    }
+
+    fn query_transaction(&self, txid: &[u8]) -> Result<bool> {
+        let res = self
+            .config
+            .horizon()
+            .query_transaction(txid, FETCH_TIMEOUT)?;
+        Ok(res.successful)
+    }
+
+    fn submit_transaction(&self, envelope: TransactionEnvelope) -> Result<SubmitResult> {
+        match self
+            .config
+            .horizon()
+            .submit_transaction(envelope, FETCH_TIMEOUT)
+        {
+            Ok(res) => Ok(SubmitResult::accepted(res.ledger, res.fee_charged)),
+            Err(FetchError::UnexpectedResponseStatus { status: 400, body }) => {
+                SubmitResult::from_problem(&body)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// In-memory ledger that serves accounts from Horizon JSON fixtures.
+pub struct MemoryHorizon {
+    config: NetworkConfig,
+    accounts: Mutex<HashMap<AccountId, String>>,
+    published: Mutex<HashSet<Vec<u8>>>,
+    submitted: Mutex<Vec<Vec<u8>>>,
+    submit_result: Mutex<SubmitResult>,
+}
+
+impl MemoryHorizon {
+    pub fn new(config: NetworkConfig) -> Self {
+        MemoryHorizon {
+            config,
+            accounts: Mutex::new(HashMap::new()),
+            published: Mutex::new(HashSet::new()),
+            submitted: Mutex::new(Vec::new()),
+            submit_result: Mutex::new(SubmitResult::accepted(1, MIN_FEE as i64)),
+        }
+    }
+
+    /// Add or replace account with its JSON representation as returned by Horizon
+    pub fn insert_account(&self, account: AccountId, json: &str) {
+        self.accounts
+            .lock()
+            .unwrap()
+            .insert(account, json.to_owned());
+    }
+
+    pub fn publish(&self, txid: &[u8]) {
+        self.published.lock().unwrap().insert(txid.to_owned());
+    }
+
+    /// Result the following submissions get, by default they are accepted
+    pub fn set_submit_result(&self, result: SubmitResult) {
+        *self.submit_result.lock().unwrap() = result;
+    }
+
+    /// XDR of envelopes that were passed to `submit_transaction`
+    pub fn submitted(&self) -> Vec<Vec<u8>> {
+        self.submitted.lock().unwrap().clone()
+    }
+
+    fn account_json(&self, account: &AccountId) -> Result<String> {
+        self.accounts
+            .lock()
+            .unwrap()
+            .get(account)
+            .cloned()
+            .ok_or(MtlError::AccountNotFound)
+    }
+}
+
+impl HorizonClient for MemoryHorizon {
+    fn network(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    fn fetch_account(&self, account: &AccountId) -> Result<AccountResponse> {
+        Ok(serde_json::from_str(&self.account_json(account)?)?)
+    }
+
+    fn fetch_next_sequence_number(&self, account: &AccountId) -> Result<i64> {
+        let json: serde_json::Value = serde_json::from_str(&self.account_json(account)?)?;
+        let sequence = json["sequence"]
+            .as_str()
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or(MtlError::AccountNotFound)?;
+        Ok(sequence + 1)
+    }
+
+    fn query_transaction(&self, txid: &[u8]) -> Result<bool> {
+        Ok(self.published.lock().unwrap().contains(txid))
+    }
+
+    fn submit_transaction(&self, envelope: TransactionEnvelope) -> Result<SubmitResult> {
+        self.submitted.lock().unwrap().push(envelope.to_xdr());
+        Ok(self.submit_result.lock().unwrap().clone())
+    }
+}
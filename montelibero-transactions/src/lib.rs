@@ -1,12 +1,15 @@
 pub mod account;
+pub mod auth;
 pub mod constants;
 pub mod error;
+pub mod horizon;
+pub mod network;
+pub mod registry;
+pub mod risk;
+pub mod signers;
+pub mod status;
+pub mod strkey;
+pub mod submit;
+pub mod summary;
+pub mod threshold;
 pub mod transaction;
-
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
-    }
-}
@@ -0,0 +1,45 @@
+use super::constants::*;
+use serde::Deserialize;
+use substrate_stellar_sdk::{horizon::Horizon, network::Network};
+
+/// Stellar network the transactions are built for: the Horizon server to
+/// query and the passphrase that is mixed into transaction hashes.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NetworkConfig {
+    pub horizon: String,
+    pub passphrase: String,
+}
+
+impl NetworkConfig {
+    pub fn mainnet() -> Self {
+        NetworkConfig {
+            horizon: HORIZON_MAINNET.to_owned(),
+            passphrase: PUBLIC_PASSPHRASE.to_owned(),
+        }
+    }
+
+    pub fn testnet() -> Self {
+        NetworkConfig {
+            horizon: HORIZON_TESTNET.to_owned(),
+            passphrase: TESTNET_PASSPHRASE.to_owned(),
+        }
+    }
+
+    pub fn horizon(&self) -> Horizon {
+        Horizon::new(&self.horizon)
+    }
+
+    pub fn network(&self) -> Network {
+        Network::new(self.passphrase.as_bytes())
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        self.passphrase == TESTNET_PASSPHRASE
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig::mainnet()
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Deserialize;
+use std::fs::File;
+use substrate_stellar_sdk::{AccountId, PublicKey, StellarSdkError};
+use thiserror::Error;
+
+/// Account that is operated by the foundation through the multisig service
+#[derive(Debug, Clone)]
+pub struct ManagedAccount {
+    pub account: AccountId,
+    pub label: String,
+    pub description: String,
+}
+
+#[derive(Deserialize)]
+struct Registry {
+    accounts: Vec<RegistryEntry>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntry {
+    pubkey: String,
+    label: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Failed to decode registry: {0}")]
+    Decode(#[from] serde_json::Error),
+    #[error("Failed to decode account {0}: {1}")]
+    PublicKey(String, StellarSdkError),
+}
+
+/// Set of accounts the service accepts transactions for
+#[derive(Debug, Clone, Default)]
+pub struct AccountsRegistry {
+    accounts: Vec<ManagedAccount>,
+}
+
+impl AccountsRegistry {
+    pub fn new(accounts: Vec<ManagedAccount>) -> Self {
+        AccountsRegistry { accounts }
+    }
+
+    /// Read registry from JSON file of form `{"accounts": [{"pubkey": .., "label": .., "description": ..}]}`
+    pub fn load(file_name: &str) -> Result<Self, RegistryError> {
+        let registry: Registry = serde_json::from_reader(File::open(file_name)?)?;
+        let mut accounts = Vec::new();
+        for entry in registry.accounts {
+            let account = PublicKey::from_encoding(&entry.pubkey)
+                .map_err(|e| RegistryError::PublicKey(entry.pubkey.clone(), e))?;
+            accounts.push(ManagedAccount {
+                account,
+                label: entry.label,
+                description: entry.description,
+            });
+        }
+        Ok(AccountsRegistry { accounts })
+    }
+
+    pub fn get(&self, account: &AccountId) -> Option<&ManagedAccount> {
+        self.accounts.iter().find(|a| a.account == *account)
+    }
+
+    pub fn contains(&self, account: &AccountId) -> bool {
+        self.get(account).is_some()
+    }
+
+    pub fn accounts(&self) -> &[ManagedAccount] {
+        &self.accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_registry() {
+        let registry = AccountsRegistry::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/accounts.json"
+        ))
+        .unwrap();
+        let foundation =
+            PublicKey::from_encoding("GDX23CPGMQ4LN55VGEDVFZPAJMAUEHSHAMJ2GMCU2ZSHN5QF4TMZYPIS")
+                .unwrap();
+        let issuer =
+            PublicKey::from_encoding("GACKTN5DAZGWXRWB2WLM6OPBDHAMT6SJNGLJZPQMEZBUR4JUGBX2UK7V")
+                .unwrap();
+        assert_eq!(registry.accounts().len(), 2);
+        assert_eq!(
+            registry.get(&foundation).map(|a| a.label.as_str()),
+            Some("MTL Foundation")
+        );
+        assert_eq!(
+            registry.get(&issuer).map(|a| a.description.as_str()),
+            Some("")
+        );
+    }
+}
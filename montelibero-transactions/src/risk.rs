@@ -0,0 +1,221 @@
+use super::summary::{
+    account, account_flags, amount, asset, asset_code, muxed_account, signer_key,
+};
+use serde::Serialize;
+use substrate_stellar_sdk::{
+    types::{AssetCode, OperationBody},
+    MuxedAccount, Operation, Transaction,
+};
+
+/// Category of an operation that touches account control or funds of others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskKind {
+    SignerChange,
+    MasterWeightChange,
+    ThresholdChange,
+    AccountMerge,
+    TrustLineFlags,
+    Clawback,
+    HomeDomainChange,
+    AccountFlagsChange,
+}
+
+/// Warning that signers should notice before signing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RiskFlag {
+    pub kind: RiskKind,
+    /// Position of the operation starting from 1
+    pub operation: usize,
+    /// Account the operation is applied to
+    pub account: String,
+    pub message: String,
+}
+
+pub fn transaction_risks(tx: &Transaction) -> Vec<RiskFlag> {
+    tx.operations
+        .get_vec()
+        .iter()
+        .enumerate()
+        .flat_map(|(i, op)| operation_risks(i, op, &tx.source_account))
+        .collect()
+}
+
+pub fn operation_risks(index: usize, op: &Operation, tx_source: &MuxedAccount) -> Vec<RiskFlag> {
+    let source = muxed_account(op.source_account.as_ref().unwrap_or(tx_source));
+    let mut risks = Vec::new();
+    let mut flag = |kind, message: String| {
+        risks.push(RiskFlag {
+            kind,
+            operation: index + 1,
+            account: source.clone(),
+            message,
+        })
+    };
+    match &op.body {
+        OperationBody::SetOptions(op) => {
+            if let Some(signer) = &op.signer {
+                let message = if signer.weight == 0 {
+                    format!("removes signer {}", signer_key(&signer.key))
+                } else {
+                    format!(
+                        "sets signer {} with weight {}",
+                        signer_key(&signer.key),
+                        signer.weight
+                    )
+                };
+                flag(RiskKind::SignerChange, message);
+            }
+            if let Some(weight) = op.master_weight {
+                flag(
+                    RiskKind::MasterWeightChange,
+                    format!("sets master key weight to {}", weight),
+                );
+            }
+            let thresholds = [
+                ("low", op.low_threshold),
+                ("medium", op.med_threshold),
+                ("high", op.high_threshold),
+            ];
+            for (name, threshold) in thresholds.iter() {
+                if let Some(threshold) = threshold {
+                    flag(
+                        RiskKind::ThresholdChange,
+                        format!("sets {} threshold to {}", name, threshold),
+                    );
+                }
+            }
+            if let Some(domain) = &op.home_domain {
+                flag(
+                    RiskKind::HomeDomainChange,
+                    format!(
+                        "sets home domain to '{}'",
+                        String::from_utf8_lossy(domain.get_vec())
+                    ),
+                );
+            }
+            if let Some(flags) = op.set_flags.filter(|f| *f != 0) {
+                flag(
+                    RiskKind::AccountFlagsChange,
+                    format!("sets account flags {}", account_flags(flags)),
+                );
+            }
+            if let Some(flags) = op.clear_flags.filter(|f| *f != 0) {
+                flag(
+                    RiskKind::AccountFlagsChange,
+                    format!("clears account flags {}", account_flags(flags)),
+                );
+            }
+        }
+        OperationBody::AccountMerge(destination) => flag(
+            RiskKind::AccountMerge,
+            format!("merges the account into {}", muxed_account(destination)),
+        ),
+        OperationBody::AllowTrust(op) => {
+            let code = match &op.asset {
+                AssetCode::AssetTypeCreditAlphanum4(c) => asset_code(c),
+                AssetCode::AssetTypeCreditAlphanum12(c) => asset_code(c),
+                _ => "unknown".to_owned(),
+            };
+            flag(
+                RiskKind::TrustLineFlags,
+                format!(
+                    "sets authorization of {} trustline of {} to {}",
+                    code,
+                    account(&op.trustor),
+                    op.authorize
+                ),
+            );
+        }
+        OperationBody::SetTrustLineFlags(op) => flag(
+            RiskKind::TrustLineFlags,
+            format!(
+                "changes {} trustline flags of {} (set {}, clear {})",
+                asset(&op.asset),
+                account(&op.trustor),
+                op.set_flags,
+                op.clear_flags
+            ),
+        ),
+        OperationBody::Clawback(op) => flag(
+            RiskKind::Clawback,
+            format!(
+                "claws back {} {} from {}",
+                amount(op.amount),
+                asset(&op.asset),
+                muxed_account(&op.from)
+            ),
+        ),
+        OperationBody::ClawbackClaimableBalance(_) => flag(
+            RiskKind::Clawback,
+            "claws back a claimable balance".to_owned(),
+        ),
+        _ => {}
+    }
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use substrate_stellar_sdk::{
+        types::{BumpSequenceOp, SetOptionsOp, Signer, SignerKey},
+        IntoMuxedAccountId,
+    };
+
+    const SOURCE: &str = "GDFJHLAXAUMHA4OWPOB4P7YO72AQR2HMIUYFOXLXE2DZGM633K7HZDQP";
+    const SIGNER: &str = "GCFIRY65OQE7DFP5KLNS2PF2LVZMUZYJX4OZIEQ36N2IQANUB5XVYOJR";
+
+    fn set_options(signer: Option<Signer>, master_weight: Option<u32>) -> Operation {
+        Operation {
+            source_account: None,
+            body: OperationBody::SetOptions(SetOptionsOp {
+                inflation_dest: None,
+                clear_flags: None,
+                set_flags: None,
+                master_weight,
+                low_threshold: None,
+                med_threshold: None,
+                high_threshold: Some(3),
+                home_domain: None,
+                signer,
+            }),
+        }
+    }
+
+    #[test]
+    fn flags_signer_removal() {
+        let source = SOURCE.as_bytes().into_muxed_account_id().unwrap();
+        let key = match SIGNER.as_bytes().into_muxed_account_id().unwrap() {
+            MuxedAccount::KeyTypeEd25519(k) => k,
+            _ => unreachable!(),
+        };
+        let signer = Signer {
+            key: SignerKey::SignerKeyTypeEd25519(key),
+            weight: 0,
+        };
+        let risks = operation_risks(1, &set_options(Some(signer), Some(0)), &source);
+        let kinds: Vec<RiskKind> = risks.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RiskKind::SignerChange,
+                RiskKind::MasterWeightChange,
+                RiskKind::ThresholdChange
+            ]
+        );
+        assert_eq!(risks[0].operation, 2);
+        assert_eq!(risks[0].account, SOURCE);
+        assert_eq!(risks[0].message, format!("removes signer {}", SIGNER));
+    }
+
+    #[test]
+    fn bump_sequence_is_not_risky() {
+        let source = SOURCE.as_bytes().into_muxed_account_id().unwrap();
+        let op = Operation {
+            source_account: None,
+            body: OperationBody::BumpSequence(BumpSequenceOp { bump_to: 1 }),
+        };
+        assert!(operation_risks(0, &op, &source).is_empty());
+    }
+}
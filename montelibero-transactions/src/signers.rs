@@ -0,0 +1,153 @@
+use super::account::*;
+use super::error::*;
+use super::threshold::ThresholdLevel;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use substrate_stellar_sdk::{types::SetOptionsOp, AccountId};
+
+/// Weight of a signer before and after the transaction, zero means the key
+/// is not a signer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerChange {
+    pub key: MtlSignerKey,
+    pub before: i32,
+    pub after: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thresholds {
+    pub low: u8,
+    pub medium: u8,
+    pub high: u8,
+}
+
+impl Thresholds {
+    /// Weight of signatures the account needs for the threshold level. Zero
+    /// thresholds still require at least one signature.
+    pub fn required_weight(&self, level: ThresholdLevel) -> u8 {
+        let threshold = match level {
+            ThresholdLevel::Low => self.low,
+            ThresholdLevel::Medium => self.medium,
+            ThresholdLevel::High => self.high,
+        };
+        u8::max(threshold, 1)
+    }
+}
+
+fn to_weight(value: u32) -> Result<u8> {
+    u8::try_from(value).map_err(|_| MtlError::WeightOverflow(value))
+}
+
+/// Signers and thresholds of an account, either current or the ones that
+/// will be set after the transaction is applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerSet {
+    pub signers: Vec<(MtlSignerKey, i32)>,
+    pub thresholds: Thresholds,
+}
+
+impl SignerSet {
+    pub fn from_account(account: &AccountResponse) -> Result<Self> {
+        Ok(SignerSet {
+            signers: get_account_signers(account)?,
+            thresholds: Thresholds {
+                low: account.thresholds.low_threshold,
+                medium: account.thresholds.med_threshold,
+                high: account.thresholds.high_threshold,
+            },
+        })
+    }
+
+    pub fn weight(&self, key: &MtlSignerKey) -> i32 {
+        self.signers
+            .iter()
+            .find(|(pk, _)| pk == key)
+            .map_or(0, |(_, w)| *w)
+    }
+
+    fn set_weight(&mut self, key: MtlSignerKey, weight: i32) {
+        match self.signers.iter_mut().find(|(pk, _)| *pk == key) {
+            Some(entry) => entry.1 = weight,
+            None => self.signers.push((key, weight)),
+        }
+    }
+
+    /// Apply set_options the way the network does. Signers with zero weight
+    /// are removed, the master key is kept as Horizon lists it. Weights and
+    /// thresholds above 255 fail the operation on the network.
+    pub fn apply(&mut self, account: &AccountId, op: &SetOptionsOp) -> Result<()> {
+        if let Some(weight) = op.master_weight {
+            self.set_weight(
+                MtlSignerKey::Ed25519(account.clone()),
+                to_weight(weight)? as i32,
+            );
+        }
+        if let Some(threshold) = op.low_threshold {
+            self.thresholds.low = to_weight(threshold)?;
+        }
+        if let Some(threshold) = op.med_threshold {
+            self.thresholds.medium = to_weight(threshold)?;
+        }
+        if let Some(threshold) = op.high_threshold {
+            self.thresholds.high = to_weight(threshold)?;
+        }
+        if let Some(signer) = &op.signer {
+            let key = MtlSignerKey::from(&signer.key);
+            match to_weight(signer.weight)? {
+                0 => self.signers.retain(|(pk, _)| *pk != key),
+                weight => self.set_weight(key, weight as i32),
+            }
+        }
+        Ok(())
+    }
+
+    /// Weights of all current and proposed signers. Current signers go first
+    /// in their order, then the added ones.
+    pub fn diff(&self, after: &SignerSet) -> Vec<SignerChange> {
+        let mut changes: Vec<SignerChange> = self
+            .signers
+            .iter()
+            .map(|(key, weight)| SignerChange {
+                key: key.clone(),
+                before: *weight,
+                after: after.weight(key),
+            })
+            .collect();
+        for (key, weight) in after.signers.iter() {
+            if !changes.iter().any(|c| c.key == *key) {
+                changes.push(SignerChange {
+                    key: key.clone(),
+                    before: 0,
+                    after: *weight,
+                });
+            }
+        }
+        changes
+    }
+
+    pub fn total_weight(&self) -> i32 {
+        self.signers.iter().map(|(_, w)| w.clamp(&0, &255)).sum()
+    }
+
+    /// Describes why the account can't authorize transactions anymore
+    pub fn lock_out_reason(&self) -> Option<String> {
+        let total = self.total_weight();
+        if total == 0 {
+            return Some("no signers with non-zero weight remain".to_owned());
+        }
+        let thresholds = [
+            ("high", self.thresholds.high),
+            ("medium", self.thresholds.medium),
+            ("low", self.thresholds.low),
+        ];
+        for (name, threshold) in thresholds.iter() {
+            if *threshold as i32 > total {
+                return Some(format!(
+                    "{} threshold {} exceeds total signers weight {}",
+                    name, threshold, total
+                ));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,128 @@
+use super::error::*;
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle of a transaction collected by the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    /// Waiting for signatures
+    Collecting,
+    /// Has enough signatures to be submitted
+    Ready,
+    /// Sent to Horizon, the result is not known yet
+    Submitted,
+    Published,
+    /// Network rejected the transaction or it doesn't pass validation anymore
+    Failed,
+    /// Time bounds have passed
+    Expired,
+    /// Sequence number of the source account is already used
+    Superseded,
+    /// Withdrawn by a signer before it was submitted
+    Cancelled,
+}
+
+pub static ALL_STATUSES: [TxStatus; 8] = [
+    TxStatus::Collecting,
+    TxStatus::Ready,
+    TxStatus::Submitted,
+    TxStatus::Published,
+    TxStatus::Failed,
+    TxStatus::Expired,
+    TxStatus::Superseded,
+    TxStatus::Cancelled,
+];
+
+impl TxStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxStatus::Collecting => "collecting",
+            TxStatus::Ready => "ready",
+            TxStatus::Submitted => "submitted",
+            TxStatus::Published => "published",
+            TxStatus::Failed => "failed",
+            TxStatus::Expired => "expired",
+            TxStatus::Superseded => "superseded",
+            TxStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        ALL_STATUSES
+            .iter()
+            .find(|s| s.name() == name)
+            .copied()
+            .ok_or_else(|| MtlError::UnknownStatus(name.to_owned()))
+    }
+
+    /// The transaction can't change anymore
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TxStatus::Published | TxStatus::Expired | TxStatus::Superseded | TxStatus::Cancelled
+        )
+    }
+
+    pub fn can_transition_to(&self, next: TxStatus) -> bool {
+        use TxStatus::*;
+        match self {
+            Collecting | Ready | Failed => true,
+            Submitted => matches!(next, Published | Failed | Expired | Superseded),
+            Published | Expired | Superseded | Cancelled => false,
+        }
+    }
+
+    /// Check the transition, staying in the same status is allowed
+    pub fn transition(&self, next: TxStatus) -> Result<TxStatus> {
+        if *self == next || self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(MtlError::StatusTransition(*self, next))
+        }
+    }
+}
+
+impl std::fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for status in ALL_STATUSES.iter() {
+            assert_eq!(TxStatus::from_name(status.name()).unwrap(), *status);
+        }
+        assert!(TxStatus::from_name("lost").is_err());
+    }
+
+    #[test]
+    fn final_statuses_are_terminal() {
+        for status in ALL_STATUSES.iter().filter(|s| s.is_final()) {
+            for next in ALL_STATUSES.iter().filter(|n| *n != status) {
+                assert!(status.transition(*next).is_err());
+            }
+        }
+        assert_eq!(
+            TxStatus::Submitted.transition(TxStatus::Published).unwrap(),
+            TxStatus::Published
+        );
+        assert!(TxStatus::Submitted
+            .transition(TxStatus::Collecting)
+            .is_err());
+        assert!(TxStatus::Submitted.transition(TxStatus::Ready).is_err());
+        assert!(TxStatus::Submitted.transition(TxStatus::Cancelled).is_err());
+        assert_eq!(
+            TxStatus::Submitted.transition(TxStatus::Expired).unwrap(),
+            TxStatus::Expired
+        );
+        assert_eq!(
+            TxStatus::Failed.transition(TxStatus::Ready).unwrap(),
+            TxStatus::Ready
+        );
+    }
+}
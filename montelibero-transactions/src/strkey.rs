@@ -0,0 +1,100 @@
+//! Stellar strkey encoding for the key types the SDK doesn't render itself,
+//! see https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0023.md
+
+pub static VERSION_ACCOUNT_ID: u8 = 6 << 3;
+pub static VERSION_MUXED_ACCOUNT: u8 = 12 << 3;
+pub static VERSION_SIGNED_PAYLOAD: u8 = 15 << 3;
+pub static VERSION_SEED: u8 = 18 << 3;
+pub static VERSION_PRE_AUTH_TX: u8 = 19 << 3;
+pub static VERSION_SHA256_HASH: u8 = 23 << 3;
+
+static ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+/// Encode payload with the version byte and checksum into base32 string
+pub fn encode(version: u8, payload: &[u8]) -> String {
+    let mut raw = vec![version];
+    raw.extend_from_slice(payload);
+    let checksum = crc16(&raw);
+    raw.extend_from_slice(&checksum.to_le_bytes());
+
+    let mut res = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in raw {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            res.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        res.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    res
+}
+
+/// Decode base32 string and return its version byte and payload. Returns
+/// `None` if the string is malformed or the checksum doesn't match.
+pub fn decode(encoded: &str) -> Option<(u8, Vec<u8>)> {
+    let mut raw = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.bytes() {
+        let value = ALPHABET.iter().position(|a| *a == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            raw.push((buffer >> bits) as u8);
+        }
+    }
+    if buffer & ((1 << bits) - 1) != 0 || raw.len() < 3 {
+        return None;
+    }
+    let (data, checksum) = raw.split_at(raw.len() - 2);
+    if crc16(data).to_le_bytes() != checksum {
+        return None;
+    }
+    Some((data[0], data[1..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_account_id() {
+        assert_eq!(
+            encode(VERSION_ACCOUNT_ID, &[0; 32]),
+            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF"
+        );
+    }
+
+    #[test]
+    fn decodes_what_encodes() {
+        let key = "GCFIRY65OQE7DFP5KLNS2PF2LVZMUZYJX4OZIEQ36N2IQANUB5XVYOJR";
+        let (version, payload) = decode(key).unwrap();
+        assert_eq!(version, VERSION_ACCOUNT_ID);
+        assert_eq!(encode(version, &payload), key);
+        assert_eq!(
+            decode("GCFIRY65OQE7DFP5KLNS2PF2LVZMUZYJX4OZIEQ36N2IQANUB5XVYOJA"),
+            None
+        );
+    }
+}
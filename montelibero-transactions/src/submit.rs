@@ -0,0 +1,174 @@
+use super::error::*;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of submitting a transaction to the network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubmitResult {
+    pub successful: bool,
+    /// Ledger the transaction was included in
+    pub ledger: Option<u32>,
+    /// Fee in stroops the source paid
+    pub fee_charged: Option<i64>,
+    /// Result code of the transaction as Horizon names it, e.g. `tx_bad_auth`
+    pub transaction_code: String,
+    /// Result codes of the operations, empty when they weren't applied
+    pub operation_codes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Problem {
+    extras: Option<ProblemExtras>,
+}
+
+#[derive(Deserialize)]
+struct ProblemExtras {
+    result_codes: ResultCodes,
+}
+
+#[derive(Deserialize)]
+struct ResultCodes {
+    transaction: String,
+    #[serde(default)]
+    operations: Vec<String>,
+}
+
+impl SubmitResult {
+    pub fn accepted(ledger: u32, fee_charged: i64) -> Self {
+        SubmitResult {
+            successful: true,
+            ledger: Some(ledger),
+            fee_charged: Some(fee_charged),
+            transaction_code: "tx_success".to_owned(),
+            operation_codes: Vec::new(),
+        }
+    }
+
+    pub fn rejected(transaction_code: &str, operation_codes: &[&str]) -> Self {
+        SubmitResult {
+            successful: false,
+            ledger: None,
+            fee_charged: None,
+            transaction_code: transaction_code.to_owned(),
+            operation_codes: operation_codes.iter().map(|c| (*c).to_owned()).collect(),
+        }
+    }
+
+    /// Parse problem response Horizon returns for rejected transactions, see
+    /// https://developers.stellar.org/api/errors/http-status-codes/horizon-specific/transaction-failed/
+    pub fn from_problem(body: &[u8]) -> Result<Self> {
+        let problem: Problem = serde_json::from_slice(body)?;
+        let codes = problem
+            .extras
+            .ok_or(MtlError::UnexpectedSubmitResponse)?
+            .result_codes;
+        Ok(SubmitResult {
+            successful: false,
+            ledger: None,
+            fee_charged: None,
+            transaction_code: codes.transaction,
+            operation_codes: codes.operations,
+        })
+    }
+
+    /// Result codes decoded into readable text, one line per code
+    pub fn describe(&self) -> Vec<String> {
+        let mut res = vec![format!(
+            "{}: {}",
+            self.transaction_code,
+            result_code_text(&self.transaction_code)
+        )];
+        for (i, code) in self.operation_codes.iter().enumerate() {
+            res.push(format!(
+                "operation #{} {}: {}",
+                i + 1,
+                code,
+                result_code_text(code)
+            ));
+        }
+        res
+    }
+}
+
+/// Meaning of transaction and common operation result codes, see
+/// https://developers.stellar.org/docs/glossary/transactions/#result-codes
+pub fn result_code_text(code: &str) -> &'static str {
+    match code {
+        "tx_success" => "transaction succeeded",
+        "tx_failed" => "one of the operations failed",
+        "tx_too_early" => "ledger close time is before the time bounds",
+        "tx_too_late" => "ledger close time is after the time bounds",
+        "tx_missing_operation" => "no operation was specified",
+        "tx_bad_seq" => "sequence number does not match source account",
+        "tx_bad_auth" => "too few valid signatures or wrong network",
+        "tx_insufficient_balance" => "fee would bring account below reserve",
+        "tx_no_source_account" => "source account not found",
+        "tx_insufficient_fee" => "fee is too small",
+        "tx_bad_auth_extra" => "unused signatures attached to transaction",
+        "tx_internal_error" => "an unknown error occurred",
+        "tx_not_supported" => "transaction type not supported",
+        "tx_fee_bump_inner_failed" => "fee bump inner transaction failed",
+        "tx_bad_sponsorship" => "sponsorship not confirmed",
+        "op_success" => "operation succeeded",
+        "op_inner" => "operation failed",
+        "op_bad_auth" => "too few valid signatures or wrong network",
+        "op_no_source_account" | "op_no_account" => "source account was not found",
+        "op_not_supported" => "operation is not supported",
+        "op_too_many_subentries" => "max number of subentries already reached",
+        "op_exceeded_work_limit" => "operation did too much work",
+        "op_too_many_sponsoring" => "account is sponsoring too many entries",
+        "op_malformed" => "operation is malformed",
+        "op_underfunded" => "not enough funds in source account",
+        "op_low_reserve" => "would create an account below the min reserve",
+        "op_line_full" => "destination would go above its trustline limit",
+        "op_no_trust" => "destination is missing a trustline for the asset",
+        "op_not_authorized" => "destination is not authorized to hold the asset",
+        "op_no_destination" => "destination account does not exist",
+        "op_no_issuer" => "issuer of the asset does not exist",
+        "op_already_exists" => "account already exists",
+        "op_bad_flags" => "flags are invalid",
+        "op_cant_change" => "flags can't be changed",
+        "op_has_sub_entries" => "account has trustlines, offers or data entries",
+        "op_immutable_set" => "source account has auth immutable flag",
+        "op_too_many_signers" => "max number of signers already reached",
+        "op_threshold_out_of_range" => "threshold is out of range",
+        "op_bad_signer" => "signer is not allowed",
+        "op_dest_full" => "destination balance would overflow",
+        "op_seqnum_too_far" => "sequence number is too far in the future",
+        _ => "unknown result code",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_horizon_problem() {
+        let body = br#"{
+            "type": "https://stellar.org/horizon-errors/transaction_failed",
+            "title": "Transaction Failed",
+            "status": 400,
+            "extras": {
+                "envelope_xdr": "AAAA",
+                "result_codes": {
+                    "transaction": "tx_failed",
+                    "operations": ["op_success", "op_underfunded"]
+                },
+                "result_xdr": "AAAA"
+            }
+        }"#;
+        let result = SubmitResult::from_problem(body).unwrap();
+        assert_eq!(
+            result,
+            SubmitResult::rejected("tx_failed", &["op_success", "op_underfunded"])
+        );
+        assert_eq!(
+            result.describe(),
+            vec![
+                "tx_failed: one of the operations failed",
+                "operation #1 op_success: operation succeeded",
+                "operation #2 op_underfunded: not enough funds in source account",
+            ]
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use super::account::MtlSignerKey;
+use super::strkey;
+use super::threshold::operation_name;
+use serde::Serialize;
+use substrate_stellar_sdk::{
+    types::{
+        Asset, ChangeTrustAsset, ClaimPredicate, ClaimableBalanceId, Claimant, Memo, OperationBody,
+        Price, SignerKey,
+    },
+    AccountId, MuxedAccount, Operation, Transaction,
+};
+
+/// Named value of an operation or transaction attribute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SummaryField {
+    pub name: &'static str,
+    pub value: String,
+}
+
+/// Human readable description of a single operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OperationSummary {
+    /// Position of the operation starting from 1
+    pub number: usize,
+    pub kind: &'static str,
+    /// Operation source if it overrides the transaction one
+    pub source_account: Option<String>,
+    pub fields: Vec<SummaryField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionSummary {
+    pub source_account: String,
+    pub fee: u32,
+    pub seq_num: i64,
+    pub memo: Option<String>,
+    pub min_time: Option<u64>,
+    pub max_time: Option<u64>,
+    pub operations: Vec<OperationSummary>,
+}
+
+pub fn summarize_transaction(tx: &Transaction) -> TransactionSummary {
+    let (min_time, max_time) = match &tx.time_bounds {
+        None => (None, None),
+        Some(bounds) => (
+            Some(bounds.min_time).filter(|t| *t > 0),
+            Some(bounds.max_time).filter(|t| *t > 0),
+        ),
+    };
+    TransactionSummary {
+        source_account: muxed_account(&tx.source_account),
+        fee: tx.fee,
+        seq_num: tx.seq_num,
+        memo: memo(&tx.memo),
+        min_time,
+        max_time,
+        operations: tx
+            .operations
+            .get_vec()
+            .iter()
+            .enumerate()
+            .map(|(i, op)| summarize_operation(i, op))
+            .collect(),
+    }
+}
+
+pub fn summarize_operation(index: usize, op: &Operation) -> OperationSummary {
+    OperationSummary {
+        number: index + 1,
+        kind: operation_name(&op.body),
+        source_account: op.source_account.as_ref().map(muxed_account),
+        fields: operation_fields(&op.body),
+    }
+}
+
+fn field<T: Into<String>>(name: &'static str, value: T) -> SummaryField {
+    SummaryField {
+        name,
+        value: value.into(),
+    }
+}
+
+fn operation_fields(body: &OperationBody) -> Vec<SummaryField> {
+    match body {
+        OperationBody::CreateAccount(op) => vec![
+            field("destination", account(&op.destination)),
+            field("starting_balance", amount(op.starting_balance)),
+        ],
+        OperationBody::Payment(op) => vec![
+            field("destination", muxed_account(&op.destination)),
+            field("asset", asset(&op.asset)),
+            field("amount", amount(op.amount)),
+        ],
+        OperationBody::PathPaymentStrictReceive(op) => vec![
+            field("destination", muxed_account(&op.destination)),
+            field("send_asset", asset(&op.send_asset)),
+            field("send_max", amount(op.send_max)),
+            field("dest_asset", asset(&op.dest_asset)),
+            field("dest_amount", amount(op.dest_amount)),
+            field("path", path(op.path.get_vec())),
+        ],
+        OperationBody::PathPaymentStrictSend(op) => vec![
+            field("destination", muxed_account(&op.destination)),
+            field("send_asset", asset(&op.send_asset)),
+            field("send_amount", amount(op.send_amount)),
+            field("dest_asset", asset(&op.dest_asset)),
+            field("dest_min", amount(op.dest_min)),
+            field("path", path(op.path.get_vec())),
+        ],
+        OperationBody::ManageSellOffer(op) => vec![
+            field("selling", asset(&op.selling)),
+            field("buying", asset(&op.buying)),
+            field("amount", amount(op.amount)),
+            field("price", price(&op.price)),
+            field("offer_id", offer_id(op.offer_id)),
+        ],
+        OperationBody::ManageBuyOffer(op) => vec![
+            field("selling", asset(&op.selling)),
+            field("buying", asset(&op.buying)),
+            field("buy_amount", amount(op.buy_amount)),
+            field("price", price(&op.price)),
+            field("offer_id", offer_id(op.offer_id)),
+        ],
+        OperationBody::CreatePassiveSellOffer(op) => vec![
+            field("selling", asset(&op.selling)),
+            field("buying", asset(&op.buying)),
+            field("amount", amount(op.amount)),
+            field("price", price(&op.price)),
+        ],
+        OperationBody::SetOptions(op) => {
+            let mut fields = Vec::new();
+            if let Some(dest) = &op.inflation_dest {
+                fields.push(field("inflation_dest", account(dest)));
+            }
+            if let Some(flags) = op.clear_flags {
+                fields.push(field("clear_flags", account_flags(flags)));
+            }
+            if let Some(flags) = op.set_flags {
+                fields.push(field("set_flags", account_flags(flags)));
+            }
+            if let Some(weight) = op.master_weight {
+                fields.push(field("master_weight", weight.to_string()));
+            }
+            if let Some(threshold) = op.low_threshold {
+                fields.push(field("low_threshold", threshold.to_string()));
+            }
+            if let Some(threshold) = op.med_threshold {
+                fields.push(field("med_threshold", threshold.to_string()));
+            }
+            if let Some(threshold) = op.high_threshold {
+                fields.push(field("high_threshold", threshold.to_string()));
+            }
+            if let Some(domain) = &op.home_domain {
+                fields.push(field("home_domain", text(domain.get_vec())));
+            }
+            if let Some(signer) = &op.signer {
+                fields.push(field("signer_key", signer_key(&signer.key)));
+                fields.push(field("signer_weight", signer.weight.to_string()));
+            }
+            fields
+        }
+        OperationBody::ChangeTrust(op) => vec![
+            field("line", change_trust_asset(&op.line)),
+            field("limit", amount(op.limit)),
+        ],
+        OperationBody::AccountMerge(destination) => {
+            vec![field("destination", muxed_account(destination))]
+        }
+        OperationBody::ManageData(op) => vec![
+            field("name", text(op.data_name.get_vec())),
+            match &op.data_value {
+                None => field("value", "(deleted)"),
+                Some(value) => field("value", text(value.get_vec())),
+            },
+        ],
+        OperationBody::BumpSequence(op) => vec![field("bump_to", op.bump_to.to_string())],
+        OperationBody::CreateClaimableBalance(op) => {
+            let mut fields = vec![
+                field("asset", asset(&op.asset)),
+                field("amount", amount(op.amount)),
+            ];
+            for claimant in op.claimants.get_vec() {
+                if let Claimant::ClaimantTypeV0(c) = claimant {
+                    fields.push(field(
+                        "claimant",
+                        format!("{} ({})", account(&c.destination), predicate(&c.predicate)),
+                    ));
+                }
+            }
+            fields
+        }
+        OperationBody::ClaimClaimableBalance(op) => {
+            vec![field("balance_id", balance_id(&op.balance_id))]
+        }
+        OperationBody::ClawbackClaimableBalance(op) => {
+            vec![field("balance_id", balance_id(&op.balance_id))]
+        }
+        _ => vec![],
+    }
+}
+
+pub fn account(account: &AccountId) -> String {
+    std::str::from_utf8(&account.to_encoding())
+        .unwrap()
+        .to_owned()
+}
+
+pub fn muxed_account(account: &MuxedAccount) -> String {
+    match account {
+        MuxedAccount::KeyTypeEd25519(k) => strkey::encode(strkey::VERSION_ACCOUNT_ID, k),
+        MuxedAccount::KeyTypeMuxedEd25519(m) => {
+            let mut payload = m.ed25519.to_vec();
+            payload.extend_from_slice(&m.id.to_be_bytes());
+            strkey::encode(strkey::VERSION_MUXED_ACCOUNT, &payload)
+        }
+        _ => "unknown".to_owned(),
+    }
+}
+
+/// Amount in stroops formatted the way Horizon does
+pub fn amount(stroops: i64) -> String {
+    let sign = if stroops < 0 { "-" } else { "" };
+    let abs = stroops.unsigned_abs();
+    format!("{}{}.{:07}", sign, abs / 10_000_000, abs % 10_000_000)
+}
+
+pub(crate) fn asset_code(code: &[u8]) -> String {
+    let len = code.iter().position(|c| *c == 0).unwrap_or(code.len());
+    String::from_utf8_lossy(&code[..len]).into_owned()
+}
+
+pub fn asset(asset: &Asset) -> String {
+    match asset {
+        Asset::AssetTypeNative => "XLM".to_owned(),
+        Asset::AssetTypeCreditAlphanum4(a) => {
+            format!("{}:{}", asset_code(&a.asset_code), account(&a.issuer))
+        }
+        Asset::AssetTypeCreditAlphanum12(a) => {
+            format!("{}:{}", asset_code(&a.asset_code), account(&a.issuer))
+        }
+        _ => "unknown".to_owned(),
+    }
+}
+
+fn change_trust_asset(line: &ChangeTrustAsset) -> String {
+    match line {
+        ChangeTrustAsset::AssetTypeNative => "XLM".to_owned(),
+        ChangeTrustAsset::AssetTypeCreditAlphanum4(a) => {
+            format!("{}:{}", asset_code(&a.asset_code), account(&a.issuer))
+        }
+        ChangeTrustAsset::AssetTypeCreditAlphanum12(a) => {
+            format!("{}:{}", asset_code(&a.asset_code), account(&a.issuer))
+        }
+        _ => "liquidity pool share".to_owned(),
+    }
+}
+
+fn path(assets: &[Asset]) -> String {
+    if assets.is_empty() {
+        return "direct".to_owned();
+    }
+    assets.iter().map(asset).collect::<Vec<_>>().join(" -> ")
+}
+
+fn price(price: &Price) -> String {
+    format!("{}/{}", price.n, price.d)
+}
+
+fn offer_id(id: i64) -> String {
+    if id == 0 {
+        "new offer".to_owned()
+    } else {
+        id.to_string()
+    }
+}
+
+pub fn account_flags(flags: u32) -> String {
+    let names = [
+        (1, "auth_required"),
+        (2, "auth_revocable"),
+        (4, "auth_immutable"),
+        (8, "auth_clawback_enabled"),
+    ];
+    names
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn signer_key(key: &SignerKey) -> String {
+    MtlSignerKey::from(key).to_encoding()
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// UTF-8 text as is, binary values in hex
+fn text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) if !s.chars().any(|c| c.is_control()) => s.to_owned(),
+        _ => format!("0x{}", hex(bytes)),
+    }
+}
+
+fn memo(memo: &Memo) -> Option<String> {
+    match memo {
+        Memo::MemoText(t) => Some(text(t.get_vec())),
+        Memo::MemoId(id) => Some(id.to_string()),
+        Memo::MemoHash(h) => Some(format!("hash {}", hex(h))),
+        Memo::MemoReturn(h) => Some(format!("return {}", hex(h))),
+        _ => None,
+    }
+}
+
+/// Balance id as Horizon displays it, with type prefix
+fn balance_id(id: &ClaimableBalanceId) -> String {
+    match id {
+        ClaimableBalanceId::ClaimableBalanceIdTypeV0(h) => format!("00000000{}", hex(h)),
+        _ => "unknown".to_owned(),
+    }
+}
+
+fn predicate(p: &ClaimPredicate) -> String {
+    match p {
+        ClaimPredicate::ClaimPredicateUnconditional => "unconditional".to_owned(),
+        ClaimPredicate::ClaimPredicateAnd(ps) => format!(
+            "({})",
+            ps.get_vec()
+                .iter()
+                .map(predicate)
+                .collect::<Vec<_>>()
+                .join(" and ")
+        ),
+        ClaimPredicate::ClaimPredicateOr(ps) => format!(
+            "({})",
+            ps.get_vec()
+                .iter()
+                .map(predicate)
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ),
+        ClaimPredicate::ClaimPredicateNot(inner) => match inner {
+            None => "not (unconditional)".to_owned(),
+            Some(inner) => format!("not {}", predicate(inner)),
+        },
+        ClaimPredicate::ClaimPredicateBeforeAbsoluteTime(t) => format!("before unix time {}", t),
+        ClaimPredicate::ClaimPredicateBeforeRelativeTime(t) => {
+            format!("within {} seconds", t)
+        }
+        _ => "unknown".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use substrate_stellar_sdk::{
+        types::{AlphaNum4, DataValue, ManageDataOp, PaymentOp, String64},
+        IntoMuxedAccountId, IntoPublicKey,
+    };
+
+    const ISSUER: &str = "GDFJHLAXAUMHA4OWPOB4P7YO72AQR2HMIUYFOXLXE2DZGM633K7HZDQP";
+    const DESTINATION: &str = "GCFIRY65OQE7DFP5KLNS2PF2LVZMUZYJX4OZIEQ36N2IQANUB5XVYOJR";
+
+    #[test]
+    fn formats_amounts() {
+        assert_eq!(amount(10_000_000), "1.0000000");
+        assert_eq!(amount(1), "0.0000001");
+        assert_eq!(amount(-25_000_000), "-2.5000000");
+    }
+
+    #[test]
+    fn summarizes_payment() {
+        let op = Operation {
+            source_account: None,
+            body: OperationBody::Payment(PaymentOp {
+                destination: DESTINATION.as_bytes().into_muxed_account_id().unwrap(),
+                asset: Asset::AssetTypeCreditAlphanum4(AlphaNum4 {
+                    asset_code: *b"MTL\0",
+                    issuer: ISSUER.as_bytes().into_public_key().unwrap(),
+                }),
+                amount: 15_000_000,
+            }),
+        };
+        assert_eq!(
+            summarize_operation(0, &op),
+            OperationSummary {
+                number: 1,
+                kind: "payment",
+                source_account: None,
+                fields: vec![
+                    field("destination", DESTINATION),
+                    field("asset", format!("MTL:{}", ISSUER)),
+                    field("amount", "1.5000000"),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn summarizes_binary_data() {
+        let op = Operation {
+            source_account: Some(ISSUER.as_bytes().into_muxed_account_id().unwrap()),
+            body: OperationBody::ManageData(ManageDataOp {
+                data_name: String64::new(b"key".to_vec()).unwrap(),
+                data_value: Some(DataValue::new(vec![0, 255]).unwrap()),
+            }),
+        };
+        let summary = summarize_operation(2, &op);
+        assert_eq!(summary.source_account.as_deref(), Some(ISSUER));
+        assert_eq!(
+            summary.fields,
+            vec![field("name", "key"), field("value", "0x00ff")]
+        );
+    }
+}
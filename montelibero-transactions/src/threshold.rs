@@ -0,0 +1,92 @@
+use serde::Serialize;
+use substrate_stellar_sdk::types::OperationBody;
+
+/// Threshold category of the source account an operation requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum ThresholdLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl ThresholdLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThresholdLevel::Low => "low",
+            ThresholdLevel::Medium => "medium",
+            ThresholdLevel::High => "high",
+        }
+    }
+}
+
+impl std::fmt::Display for ThresholdLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Threshold level as defined in the Stellar docs, see
+/// https://developers.stellar.org/docs/glossary/multisig/#thresholds
+pub fn operation_threshold(op: &OperationBody) -> ThresholdLevel {
+    match op {
+        OperationBody::AllowTrust(_)
+        | OperationBody::SetTrustLineFlags(_)
+        | OperationBody::BumpSequence(_)
+        | OperationBody::ClaimClaimableBalance(_)
+        | OperationBody::Inflation => ThresholdLevel::Low,
+        OperationBody::AccountMerge(_) => ThresholdLevel::High,
+        OperationBody::SetOptions(op) => {
+            if op.master_weight.is_some()
+                || op.low_threshold.is_some()
+                || op.med_threshold.is_some()
+                || op.high_threshold.is_some()
+                || op.signer.is_some()
+            {
+                ThresholdLevel::High
+            } else {
+                ThresholdLevel::Medium
+            }
+        }
+        _ => ThresholdLevel::Medium,
+    }
+}
+
+/// Name of operation as Horizon displays it
+pub fn operation_name(op: &OperationBody) -> &'static str {
+    match op {
+        OperationBody::CreateAccount(_) => "create_account",
+        OperationBody::Payment(_) => "payment",
+        OperationBody::PathPaymentStrictReceive(_) => "path_payment_strict_receive",
+        OperationBody::ManageSellOffer(_) => "manage_sell_offer",
+        OperationBody::CreatePassiveSellOffer(_) => "create_passive_sell_offer",
+        OperationBody::SetOptions(_) => "set_options",
+        OperationBody::ChangeTrust(_) => "change_trust",
+        OperationBody::AllowTrust(_) => "allow_trust",
+        OperationBody::AccountMerge(_) => "account_merge",
+        OperationBody::Inflation => "inflation",
+        OperationBody::ManageData(_) => "manage_data",
+        OperationBody::BumpSequence(_) => "bump_sequence",
+        OperationBody::ManageBuyOffer(_) => "manage_buy_offer",
+        OperationBody::PathPaymentStrictSend(_) => "path_payment_strict_send",
+        OperationBody::CreateClaimableBalance(_) => "create_claimable_balance",
+        OperationBody::ClaimClaimableBalance(_) => "claim_claimable_balance",
+        OperationBody::BeginSponsoringFutureReserves(_) => "begin_sponsoring_future_reserves",
+        OperationBody::EndSponsoringFutureReserves => "end_sponsoring_future_reserves",
+        OperationBody::RevokeSponsorship(_) => "revoke_sponsorship",
+        OperationBody::Clawback(_) => "clawback",
+        OperationBody::ClawbackClaimableBalance(_) => "clawback_claimable_balance",
+        OperationBody::SetTrustLineFlags(_) => "set_trust_line_flags",
+        OperationBody::LiquidityPoolDeposit(_) => "liquidity_pool_deposit",
+        OperationBody::LiquidityPoolWithdraw(_) => "liquidity_pool_withdraw",
+        _ => "unknown",
+    }
+}
+
+/// Threshold the envelope requires from the account that signs it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequiredThreshold {
+    pub level: ThresholdLevel,
+    /// Index and name of the operation that drives the requirement. `None`
+    /// when the envelope itself defines it, e.g. fee bumps.
+    pub operation: Option<(usize, &'static str)>,
+}
@@ -1,51 +1,106 @@
 use super::account::*;
 use super::constants::*;
 use super::error::*;
+use super::horizon::HorizonClient;
+use super::network::NetworkConfig;
+use super::registry::AccountsRegistry;
+use super::risk::{transaction_risks, RiskFlag};
+use super::signers::SignerSet;
+use super::status::TxStatus;
+use super::submit::SubmitResult;
+use super::summary::{hex, summarize_transaction, TransactionSummary};
+use super::threshold::*;
 use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 use substrate_stellar_sdk::{
-    network::PUBLIC_NETWORK,
+    compound_types::{LimitedVarArray, LimitedVarOpaque},
     types::{
-        SignatureHint, TimePoint, TransactionSignaturePayload,
-        TransactionSignaturePayloadTaggedTransaction, TransactionV1Envelope,
+        DecoratedSignature, FeeBumpTransaction, FeeBumpTransactionEnvelope,
+        FeeBumpTransactionInnerTx, OperationBody, SignatureHint, TimePoint,
+        TransactionSignaturePayload, TransactionSignaturePayloadTaggedTransaction,
+        TransactionV1Envelope,
     },
-    AccountId, IntoHash, IntoMuxedAccountId, MuxedAccount, PublicKey, Transaction,
-    TransactionEnvelope, XdrCodec,
+    AccountId, IntoHash, MuxedAccount, PublicKey, Transaction, TransactionEnvelope, XdrCodec,
 };
 
+/// Account which signatures the envelope needs
+#[derive(Debug)]
+pub struct SigningAccount {
+    pub account_id: AccountId,
+    pub threshold: RequiredThreshold,
+    /// Current signers and thresholds of the account
+    pub signers: SignerSet,
+}
+
+impl SigningAccount {
+    pub fn required_weight(&self) -> u8 {
+        self.signers
+            .thresholds
+            .required_weight(self.threshold.level)
+    }
+
+    pub fn is_signer(&self, key: &PublicKey) -> bool {
+        self.signers.weight(&MtlSignerKey::Ed25519(key.clone())) > 0
+    }
+}
+
+/// Only the author or a signer of the accounts may withdraw the transaction,
+/// cancelling is final
+pub fn may_cancel(
+    accounts: &[SigningAccount],
+    author: Option<&str>,
+    caller: Option<&PublicKey>,
+) -> bool {
+    let caller = match caller {
+        Some(caller) => caller,
+        None => return false,
+    };
+    let is_author = matches!(author, Some(a) if a.as_bytes() == &caller.to_encoding()[..]);
+    is_author || accounts.iter().any(|acc| acc.is_signer(caller))
+}
+
+/// Signing state of the envelope that is cheap to store and filter by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningProgress {
+    /// Weight collected for the account that authorizes the envelope
+    pub collected: i32,
+    /// Weight the account needs
+    pub required: i32,
+    /// Ed25519 signers of accounts lacking weight that haven't signed yet
+    pub awaiting: Vec<PublicKey>,
+}
+
+/// Outcome of checking the transaction against the network
+#[derive(Debug)]
+pub struct StatusCheck {
+    pub status: TxStatus,
+    /// Why the transaction can't be published, if it can't
+    pub reason: Option<String>,
+    /// Signing accounts as of the check, unless the transaction was settled
+    /// before they were needed
+    pub accounts: Option<Vec<SigningAccount>>,
+}
+
 #[derive(Debug, Clone)]
-pub struct MtlTransaction(TransactionV1Envelope);
-
-pub fn is_mtl_account(mtl_account: &AccountResponse, acc_id: &MuxedAccount) -> Result<bool> {
-    let mtl_foundation = MTL_FOUNDATION.as_bytes().into_muxed_account_id()?;
-    let mtl_issuerer = MTL_ISSUERER.as_bytes().into_muxed_account_id()?;
-    let mtlcity_issuerer = MTLCITY_ISSUERER.as_bytes().into_muxed_account_id()?;
-    let btc_treasury = BTC_TREASURY.as_bytes().into_muxed_account_id()?;
-    let mtl_additional = MTL_ADDITIONAL_ACCOUNT.as_bytes().into_muxed_account_id()?;
-    let btc_foundation = BTC_FOUNDATION.as_bytes().into_muxed_account_id()?;
-    let rect_foundation = MTL_RECT_ACCOUNT.as_bytes().into_muxed_account_id()?;
-    let multisig_storage = MTL_MULTISIG_STORAGE_ACCOUNT
-        .as_bytes()
-        .into_muxed_account_id()?;
-    let multisig_storage2 = MTL_MULTISIG_STORAGE_ACCOUNT2
-        .as_bytes()
-        .into_muxed_account_id()?;
-    let signers: Vec<PublicKey> = get_mtl_signers(&mtl_account)?
+pub enum MtlTransaction {
+    Tx(TransactionV1Envelope),
+    /// Fee bump envelope together with the inner transaction it pays for
+    FeeBump(Box<FeeBumpTransactionEnvelope>, TransactionV1Envelope),
+}
+
+/// Check that the account is either registered in the service or signs
+/// transactions by its own master key
+pub fn is_mtl_account(
+    registry: &AccountsRegistry,
+    mtl_account: &AccountResponse,
+    acc_id: &MuxedAccount,
+) -> Result<bool> {
+    let pubkey = account_pubkey(acc_id)?;
+    let signers: Vec<PublicKey> = get_mtl_signers(mtl_account)?
         .iter()
         .map(|s| s.0.clone())
         .collect();
-    Ok(*acc_id == mtl_foundation
-        || *acc_id == mtl_issuerer
-        || *acc_id == mtlcity_issuerer
-        || *acc_id == btc_treasury
-        || *acc_id == mtl_additional
-        || *acc_id == btc_foundation
-        || *acc_id == rect_foundation
-        || *acc_id == multisig_storage
-        || *acc_id == multisig_storage2
-        || signers
-            .iter()
-            .any(|s| account_pubkey(acc_id).unwrap() == *s))
+    Ok(registry.contains(&pubkey) || signers.contains(&pubkey))
 }
 
 fn account_pubkey(account: &MuxedAccount) -> Result<PublicKey> {
@@ -55,10 +110,14 @@ fn account_pubkey(account: &MuxedAccount) -> Result<PublicKey> {
     }
 }
 
-pub fn guard_mtl_account(tx: &Transaction) -> Result<()> {
-    let acc = &tx.source_account;
-    let pubkey = get_account(account_pubkey(acc)?)?;
-    if !is_mtl_account(&pubkey, acc)? {
+/// Check that the account that authorizes the envelope is MTL related
+pub fn guard_mtl_account(
+    horizon: &dyn HorizonClient,
+    registry: &AccountsRegistry,
+    acc: &MuxedAccount,
+) -> Result<()> {
+    let account = get_account(horizon, account_pubkey(acc)?)?;
+    if !is_mtl_account(registry, &account, acc)? {
         return Err(MtlError::WrongSourceAccount);
     }
     Ok(())
@@ -71,24 +130,47 @@ pub fn guard_fee(tx: &Transaction) -> Result<()> {
     Ok(())
 }
 
+/// Fee bump has to pay more than the inner transaction offers, but still
+/// stay in the standard limits. The inner transaction offers a standard fee.
+pub fn guard_fee_bump(tx: &FeeBumpTransaction, inner: &Transaction) -> Result<()> {
+    guard_fee(inner)?;
+    if tx.fee <= inner.fee as i64 || tx.fee > MAX_FEE as i64 {
+        return Err(MtlError::NonStandardFee);
+    }
+    Ok(())
+}
+
 /// Parse and validate a raw MTL transaction
-pub fn parse_mtl_tx<T: AsRef<[u8]>>(raw_tx: &T) -> Result<MtlTransaction> {
+pub fn parse_mtl_tx<T: AsRef<[u8]>>(
+    horizon: &dyn HorizonClient,
+    registry: &AccountsRegistry,
+    raw_tx: &T,
+) -> Result<MtlTransaction> {
     let tx_envelope = TransactionEnvelope::from_base64_xdr(raw_tx)?;
-    match tx_envelope {
-        TransactionEnvelope::EnvelopeTypeTx(envelope) => {
+    let mtx = MtlTransaction::from_envelope(tx_envelope)?;
+    match &mtx {
+        MtlTransaction::Tx(envelope) => {
             let tx = &envelope.tx;
-            guard_mtl_account(tx)?;
+            guard_mtl_account(horizon, registry, &tx.source_account)?;
             guard_fee(tx)?;
-            Ok(MtlTransaction(envelope))
         }
-        TransactionEnvelope::EnvelopeTypeTxV0(_) => Err(MtlError::DeprecatedTxVersion),
-        _ => Err(MtlError::UnsupportedTx),
+        MtlTransaction::FeeBump(envelope, inner) => {
+            let tx = &envelope.tx;
+            guard_mtl_account(horizon, registry, &tx.fee_source)?;
+            guard_mtl_account(horizon, registry, &inner.tx.source_account)?;
+            guard_fee_bump(tx, &inner.tx)?;
+        }
     }
+    Ok(mtx)
 }
 
-pub fn validate_mtl_tx<T: AsRef<[u8]>>(raw_tx: &T) -> Result<MtlTransaction> {
-    let tx = parse_mtl_tx(raw_tx)?;
-    tx.validate_create()?;
+pub fn validate_mtl_tx<T: AsRef<[u8]>>(
+    horizon: &dyn HorizonClient,
+    registry: &AccountsRegistry,
+    raw_tx: &T,
+) -> Result<MtlTransaction> {
+    let tx = parse_mtl_tx(horizon, registry, raw_tx)?;
+    tx.validate_create(horizon)?;
     Ok(tx)
 }
 
@@ -100,36 +182,192 @@ fn get_current_time() -> TimePoint {
     since_the_epoch.as_secs()
 }
 
+fn hash_payload(
+    network: &NetworkConfig,
+    tx: TransactionSignaturePayloadTaggedTransaction,
+) -> Vec<u8> {
+    let payload = TransactionSignaturePayload {
+        network_id: network.network().get_id().into_hash().unwrap(),
+        tagged_transaction: tx,
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(payload.to_xdr());
+    hasher.finalize().to_vec()
+}
+
 impl MtlTransaction {
-    pub fn txid(&self) -> Vec<u8> {
-        let payload = TransactionSignaturePayload {
-            network_id: PUBLIC_NETWORK.get_id().into_hash().unwrap(),
-            tagged_transaction: TransactionSignaturePayloadTaggedTransaction::EnvelopeTypeTx(
-                self.0.tx.clone(),
+    fn from_envelope(tx_envelope: TransactionEnvelope) -> Result<Self> {
+        match tx_envelope {
+            TransactionEnvelope::EnvelopeTypeTx(envelope) => Ok(MtlTransaction::Tx(envelope)),
+            TransactionEnvelope::EnvelopeTypeTxFeeBump(envelope) => {
+                match envelope.tx.inner_tx.clone() {
+                    FeeBumpTransactionInnerTx::EnvelopeTypeTx(inner) => {
+                        Ok(MtlTransaction::FeeBump(Box::new(envelope), inner))
+                    }
+                    _ => Err(MtlError::UnsupportedTx),
+                }
+            }
+            TransactionEnvelope::EnvelopeTypeTxV0(_) => Err(MtlError::DeprecatedTxVersion),
+            _ => Err(MtlError::UnsupportedTx),
+        }
+    }
+
+    pub fn envelope(&self) -> TransactionEnvelope {
+        match self {
+            MtlTransaction::Tx(envelope) => TransactionEnvelope::EnvelopeTypeTx(envelope.clone()),
+            MtlTransaction::FeeBump(envelope, _) => {
+                TransactionEnvelope::EnvelopeTypeTxFeeBump(envelope.as_ref().clone())
+            }
+        }
+    }
+
+    /// Hash of the outer envelope, for fee bumps it differs from the inner one
+    pub fn txid(&self, network: &NetworkConfig) -> Vec<u8> {
+        match self {
+            MtlTransaction::Tx(envelope) => hash_payload(
+                network,
+                TransactionSignaturePayloadTaggedTransaction::EnvelopeTypeTx(envelope.tx.clone()),
             ),
-        };
-        let mut hasher = Sha256::new();
-        hasher.update(payload.to_xdr());
-        hasher.finalize().to_vec()
+            MtlTransaction::FeeBump(envelope, _) => hash_payload(
+                network,
+                TransactionSignaturePayloadTaggedTransaction::EnvelopeTypeTxFeeBump(
+                    envelope.tx.clone(),
+                ),
+            ),
+        }
     }
 
-    pub fn fetch_source_account(&self) -> Result<AccountResponse> {
-        get_account(self.source_account()?)
+    pub fn is_fee_bump(&self) -> bool {
+        matches!(self, MtlTransaction::FeeBump(..))
     }
 
-    pub fn fetch_sequence_number(&self) -> Result<i64> {
-        Ok(horizon_mainnet().fetch_next_sequence_number(self.source_account()?, FETCH_TIMEOUT)?)
+    /// Transaction wrapped by the fee bump
+    pub fn inner(&self) -> Option<MtlTransaction> {
+        match self {
+            MtlTransaction::Tx(_) => None,
+            MtlTransaction::FeeBump(_, inner) => Some(MtlTransaction::Tx(inner.clone())),
+        }
     }
 
+    pub fn inner_txid(&self, network: &NetworkConfig) -> Option<Vec<u8>> {
+        self.inner().map(|tx| tx.txid(network))
+    }
+
+    /// Transaction that carries the operations
+    pub fn transaction(&self) -> &Transaction {
+        match self {
+            MtlTransaction::Tx(envelope) => &envelope.tx,
+            MtlTransaction::FeeBump(_, inner) => &inner.tx,
+        }
+    }
+
+    /// Human readable description of the transaction that carries the operations
+    pub fn summary(&self) -> TransactionSummary {
+        summarize_transaction(self.transaction())
+    }
+
+    /// Warnings about operations that touch account control
+    pub fn risks(&self) -> Vec<RiskFlag> {
+        transaction_risks(self.transaction())
+    }
+
+    pub fn fetch_source_account(&self, horizon: &dyn HorizonClient) -> Result<AccountResponse> {
+        horizon.fetch_account(&self.source_account()?)
+    }
+
+    pub fn fetch_sequence_number(&self, horizon: &dyn HorizonClient) -> Result<i64> {
+        horizon.fetch_next_sequence_number(&account_pubkey(&self.transaction().source_account)?)
+    }
+
+    /// Account which signers authorize the envelope. That is the fee source
+    /// for fee bumps and the transaction source otherwise.
     pub fn source_account(&self) -> Result<AccountId> {
-        match self.0.tx.source_account {
-            MuxedAccount::KeyTypeEd25519(k) => Ok(AccountId::PublicKeyTypeEd25519(k)),
-            _ => Err(MtlError::WrongSourceAccount),
+        match self {
+            MtlTransaction::Tx(envelope) => account_pubkey(&envelope.tx.source_account),
+            MtlTransaction::FeeBump(envelope, _) => account_pubkey(&envelope.tx.fee_source),
+        }
+    }
+
+    /// Distinct accounts that authorize the envelope together with the
+    /// highest threshold level among their operations. The envelope source
+    /// goes first and needs at least low threshold to pay the fee. Fee bumps
+    /// only require the fee source.
+    pub fn required_thresholds(&self) -> Result<Vec<(AccountId, RequiredThreshold)>> {
+        let source = self.source_account()?;
+        let mut required = vec![(
+            source.clone(),
+            RequiredThreshold {
+                level: ThresholdLevel::Low,
+                operation: None,
+            },
+        )];
+        if let MtlTransaction::Tx(envelope) = self {
+            for (i, op) in envelope.tx.operations.get_vec().iter().enumerate() {
+                let account = match &op.source_account {
+                    None => source.clone(),
+                    Some(acc) => account_pubkey(acc)?,
+                };
+                let threshold = RequiredThreshold {
+                    level: operation_threshold(&op.body),
+                    operation: Some((i, operation_name(&op.body))),
+                };
+                match required.iter_mut().find(|(acc, _)| *acc == account) {
+                    None => required.push((account, threshold)),
+                    Some((_, current)) => {
+                        if current.operation.is_none() || threshold.level > current.level {
+                            *current = threshold;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(required)
+    }
+
+    /// Threshold the envelope source has to meet
+    pub fn required_threshold(&self) -> Result<RequiredThreshold> {
+        Ok(self.required_thresholds()?.remove(0).1)
+    }
+
+    pub fn fetch_signing_accounts(
+        &self,
+        horizon: &dyn HorizonClient,
+    ) -> Result<Vec<SigningAccount>> {
+        let mut accounts = Vec::new();
+        for (account_id, threshold) in self.required_thresholds()? {
+            let account = horizon.fetch_account(&account_id)?;
+            accounts.push(SigningAccount {
+                account_id,
+                threshold,
+                signers: SignerSet::from_account(&account)?,
+            });
+        }
+        Ok(accounts)
+    }
+
+    /// Same as `fetch_signing_accounts` but with signers fetched earlier
+    pub fn known_signing_accounts(
+        &self,
+        known: &[(AccountId, SignerSet)],
+    ) -> Result<Vec<SigningAccount>> {
+        let mut accounts = Vec::new();
+        for (account_id, threshold) in self.required_thresholds()? {
+            let signers = known
+                .iter()
+                .find(|(id, _)| *id == account_id)
+                .map(|(_, signers)| signers.clone())
+                .ok_or(MtlError::AccountNotFound)?;
+            accounts.push(SigningAccount {
+                account_id,
+                threshold,
+                signers,
+            });
         }
+        Ok(accounts)
     }
 
     pub fn has_time_window(&self) -> bool {
-        match &self.0.tx.time_bounds {
+        match &self.transaction().time_bounds {
             None => true,
             Some(bounds) => {
                 let current = get_current_time();
@@ -150,6 +388,28 @@ impl MtlTransaction {
         }
     }
 
+    /// Unix time since which the network accepts the transaction
+    pub fn valid_since(&self) -> TimePoint {
+        self.transaction()
+            .time_bounds
+            .as_ref()
+            .map_or(0, |bounds| bounds.min_time)
+    }
+
+    /// Unix time after which the network rejects the transaction
+    pub fn valid_until(&self) -> Option<TimePoint> {
+        self.transaction()
+            .time_bounds
+            .as_ref()
+            .map(|bounds| bounds.max_time)
+            .filter(|max_time| *max_time > 0)
+    }
+
+    /// Whether the time bounds of the transaction have passed
+    pub fn is_expired(&self) -> bool {
+        matches!(self.valid_until(), Some(max_time) if max_time < get_current_time())
+    }
+
     pub fn guard_time_window(&self) -> Result<()> {
         if !self.has_time_window() {
             return Err(MtlError::TooLittleTimeBound);
@@ -158,88 +418,1575 @@ impl MtlTransaction {
     }
 
     /// Statefull validation if the TX is valid for future publishing
-    pub fn validate_create(&self) -> Result<()> {
-        let seq_num = self.fetch_sequence_number()?;
-        if seq_num > self.0.tx.seq_num {
+    pub fn validate_create(&self, horizon: &dyn HorizonClient) -> Result<()> {
+        let seq_num = self.fetch_sequence_number(horizon)?;
+        if seq_num > self.transaction().seq_num {
             return Err(MtlError::SequenceNumber);
         }
         self.guard_time_window()?;
-        let account = self.fetch_source_account()?;
-        let signers: Vec<PublicKey> = get_mtl_signers(&account)?
-            .iter()
-            .map(|s| s.0.clone())
-            .collect();
-        TransactionEnvelope::EnvelopeTypeTx(self.0.clone())
-            .check_signatures(&PUBLIC_NETWORK, &signers)?;
-        self.guard_excess_signatures(&account)?;
+        let accounts = self.fetch_signing_accounts(horizon)?;
+        self.guard_invalid_signatures(horizon.network(), &accounts, &[])?;
+        self.guard_excess_signatures(horizon.network(), &accounts)?;
+        self.guard_lock_out(&accounts)?;
+        if let Some(inner) = self.inner() {
+            inner.guard_inner(horizon)?;
+        }
         Ok(())
     }
 
-    /// Check that the transaction has just enough number of signatures to sign
-    pub fn guard_excess_signatures(&self, account: &AccountResponse) -> Result<()> {
-        let required = get_required_weight(account) as i32;
-        let mut accum: i32 = 0;
-        for (_, w) in self.get_signed_keys(account)? {
-            if accum >= required && accum + w >= required {
-                return Err(MtlError::SignaturesExcess);
+    /// Fee bump can't change signatures of the transaction it wraps, so the
+    /// inner transaction has to be valid and fully signed already
+    fn guard_inner(&self, horizon: &dyn HorizonClient) -> Result<()> {
+        self.validate_create(horizon)?;
+        let accounts = self.fetch_signing_accounts(horizon)?;
+        if !self.is_fully_signed(horizon.network(), &accounts)? {
+            return Err(MtlError::NotEnoughSignatures);
+        }
+        Ok(())
+    }
+
+    /// Signers and thresholds of the account after the transaction is
+    /// applied, `None` if the transaction doesn't touch them. Fee bumps apply
+    /// the operations of the inner transaction.
+    pub fn simulate_signers(&self, account: &SigningAccount) -> Result<Option<SignerSet>> {
+        let tx = self.transaction();
+        let mut signers = account.signers.clone();
+        let mut changed = false;
+        for op in tx.operations.get_vec() {
+            let source = account_pubkey(op.source_account.as_ref().unwrap_or(&tx.source_account))?;
+            if let OperationBody::SetOptions(set_options) = &op.body {
+                if source == account.account_id {
+                    signers.apply(&account.account_id, set_options)?;
+                    changed = true;
+                }
+            }
+        }
+        Ok(if changed { Some(signers) } else { None })
+    }
+
+    /// Check that every account still can authorize transactions after the
+    /// signers and thresholds changes
+    pub fn guard_lock_out(&self, accounts: &[SigningAccount]) -> Result<()> {
+        for acc in accounts {
+            if let Some(signers) = self.simulate_signers(acc)? {
+                if let Some(reason) = signers.lock_out_reason() {
+                    let account = std::str::from_utf8(&acc.account_id.to_encoding())
+                        .unwrap()
+                        .to_owned();
+                    return Err(MtlError::LockOut(account, reason));
+                }
             }
-            accum += w;
+        }
+        Ok(())
+    }
+
+    /// Check that the transaction has just enough number of signatures to sign.
+    /// A signature is excess when none of the signing accounts needs it.
+    pub fn guard_excess_signatures(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+    ) -> Result<()> {
+        let mut needed = Vec::new();
+        let mut excess = Vec::new();
+        for acc in accounts {
+            let required = acc.required_weight() as i32;
+            // Pre-auth signers don't put signatures into the envelope
+            let (pre_auth, signed): (Vec<_>, Vec<_>) = self
+                .get_signed_keys(network, &acc.signers)?
+                .into_iter()
+                .partition(|(key, _)| matches!(key, MtlSignerKey::PreAuthTx(_)));
+            let mut accum: i32 = pre_auth.iter().map(|(_, w)| w).sum();
+            for (key, w) in signed {
+                if accum >= required {
+                    excess.push(key);
+                } else {
+                    needed.push(key);
+                }
+                accum += w;
+            }
+        }
+        if excess.iter().any(|key| !needed.contains(key)) {
+            return Err(MtlError::SignaturesExcess);
         }
         Ok(())
     }
 
     pub fn into_bytes(&self) -> Vec<u8> {
-        TransactionEnvelope::EnvelopeTypeTx(self.0.clone()).to_xdr()
+        self.envelope().to_xdr()
     }
 
     pub fn into_encoding(&self) -> String {
-        std::str::from_utf8(&TransactionEnvelope::EnvelopeTypeTx(self.0.clone()).to_base64_xdr())
+        std::str::from_utf8(&self.envelope().to_base64_xdr())
             .unwrap()
             .to_owned()
     }
 
     pub fn from_bytes<T: AsRef<[u8]>>(bytes: &T) -> Result<Self> {
-        let tx_envelope = TransactionEnvelope::from_xdr(bytes)?;
-        match tx_envelope {
-            TransactionEnvelope::EnvelopeTypeTx(envelope) => Ok(MtlTransaction(envelope)),
-            TransactionEnvelope::EnvelopeTypeTxV0(_) => Err(MtlError::DeprecatedTxVersion),
-            _ => Err(MtlError::UnsupportedTx),
+        MtlTransaction::from_envelope(TransactionEnvelope::from_xdr(bytes)?)
+    }
+
+    /// Decode base64 XDR envelope without checking it against the registry
+    pub fn from_encoding<T: AsRef<[u8]>>(encoded: &T) -> Result<Self> {
+        MtlTransaction::from_envelope(TransactionEnvelope::from_base64_xdr(encoded)?)
+    }
+
+    pub fn decorated_signatures(&self) -> &[DecoratedSignature] {
+        match self {
+            MtlTransaction::Tx(envelope) => envelope.signatures.get_vec(),
+            MtlTransaction::FeeBump(envelope, _) => envelope.signatures.get_vec(),
+        }
+    }
+
+    /// Same envelope with the signatures replaced
+    pub fn with_signatures(&self, signatures: Vec<DecoratedSignature>) -> Result<Self> {
+        let signatures =
+            LimitedVarArray::new(signatures).map_err(|_| MtlError::TooManySignatures)?;
+        Ok(match self {
+            MtlTransaction::Tx(envelope) => MtlTransaction::Tx(TransactionV1Envelope {
+                signatures,
+                ..envelope.clone()
+            }),
+            MtlTransaction::FeeBump(envelope, inner) => MtlTransaction::FeeBump(
+                Box::new(FeeBumpTransactionEnvelope {
+                    signatures,
+                    ..envelope.as_ref().clone()
+                }),
+                inner.clone(),
+            ),
+        })
+    }
+
+    /// Append signature over the transaction hash made outside of the
+    /// envelope, e.g. by a hardware wallet. Fails if it doesn't verify.
+    pub fn with_detached_signature(
+        &self,
+        network: &NetworkConfig,
+        signer: &PublicKey,
+        signature: &[u8],
+    ) -> Result<Self> {
+        if signature.len() != 64 {
+            return Err(MtlError::SignatureLength(signature.len()));
+        }
+        let key = MtlSignerKey::Ed25519(signer.clone());
+        let signature = DecoratedSignature {
+            hint: signer.get_signature_hint(),
+            signature: LimitedVarOpaque::new(signature.to_vec())
+                .map_err(|_| MtlError::SignatureLength(signature.len()))?,
+        };
+        if !key.verify(&self.txid(network), &signature) {
+            return Err(MtlError::InvalidSignature(hex(&signature.hint)));
+        }
+        let mut signatures = self.decorated_signatures().to_vec();
+        if !signatures.contains(&signature) {
+            signatures.push(signature);
         }
+        self.with_signatures(signatures)
+    }
+
+    /// Union of verified signatures from envelopes of the same transaction,
+    /// e.g. uploaded by signers in parallel. Signatures of `self` go first.
+    pub fn merge_signatures(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+        others: &[MtlTransaction],
+    ) -> Result<Self> {
+        let txid = self.txid(network);
+        let mut signatures: Vec<DecoratedSignature> = Vec::new();
+        for tx in std::iter::once(self).chain(others.iter()) {
+            if tx.txid(network) != txid {
+                return Err(MtlError::UpdateContentChanged);
+            }
+            let invalid = tx.invalid_signatures(network, accounts)?;
+            for s in tx.decorated_signatures() {
+                if !invalid.contains(s) && !signatures.contains(s) {
+                    signatures.push(s.clone());
+                }
+            }
+        }
+        self.with_signatures(signatures)
+    }
+
+    /// Reduce the envelope to signatures that are enough for every account.
+    /// Signatures from `preferred`, e.g. already stored ones, are taken first,
+    /// the rest are dropped as soon as the accounts reach their thresholds.
+    pub fn trim_signatures(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+        preferred: &[DecoratedSignature],
+    ) -> Result<Self> {
+        let txid = self.txid(network);
+        let mut candidates: Vec<&DecoratedSignature> = self.decorated_signatures().iter().collect();
+        candidates.sort_by_key(|s| !preferred.contains(s));
+        let mut keep: Vec<&DecoratedSignature> = Vec::new();
+        for acc in accounts {
+            let required = acc.required_weight() as i32;
+            let signers = &acc.signers.signers;
+            let mut used = vec![false; signers.len()];
+            let mut accum: i32 = 0;
+            // Pre-auth signers and signatures kept for previous accounts are free
+            for (i, (key, w)) in signers.iter().enumerate() {
+                let satisfied = match key {
+                    MtlSignerKey::PreAuthTx(h) => h[..] == txid[..],
+                    _ => keep.iter().any(|s| key.verify(&txid, s)),
+                };
+                if satisfied {
+                    used[i] = true;
+                    accum += w;
+                }
+            }
+            for s in candidates.iter() {
+                if accum >= required {
+                    break;
+                }
+                if keep.contains(s) {
+                    continue;
+                }
+                let matched = signers
+                    .iter()
+                    .enumerate()
+                    .find(|(i, (key, _))| !used[*i] && key.verify(&txid, s));
+                if let Some((i, (_, w))) = matched {
+                    used[i] = true;
+                    accum += w;
+                    keep.push(s);
+                }
+            }
+        }
+        let trimmed = self
+            .decorated_signatures()
+            .iter()
+            .filter(|s| keep.contains(s))
+            .cloned()
+            .collect();
+        self.with_signatures(trimmed)
     }
 
     pub fn signatures(&self) -> Vec<SignatureHint> {
-        self.0.signatures.get_vec().iter().map(|s| s.hint).collect()
+        self.decorated_signatures().iter().map(|s| s.hint).collect()
     }
 
-    pub fn get_signed_keys(&self, account: &AccountResponse) -> Result<Vec<(PublicKey, i32)>> {
-        let signers = get_mtl_signers(account)?;
-        let signs: Vec<SignatureHint> =
-            self.0.signatures.get_vec().iter().map(|s| s.hint).collect();
+    /// Signers of the account the envelope satisfies. Signatures are verified
+    /// against the transaction hash, pre-auth signers count when they
+    /// authorize this very envelope, not the transaction a fee bump wraps.
+    pub fn get_signed_keys(
+        &self,
+        network: &NetworkConfig,
+        account: &SignerSet,
+    ) -> Result<Vec<(MtlSignerKey, i32)>> {
+        let signers = account.signers.clone();
+        let signs = self.decorated_signatures();
+        let txid = self.txid(network);
 
         Ok(signers
+            .into_iter()
+            .filter(|(key, _)| match key {
+                MtlSignerKey::PreAuthTx(h) => h[..] == txid[..],
+                _ => signs.iter().any(|s| key.verify(&txid, s)),
+            })
+            .collect())
+    }
+
+    /// Signatures that don't verify against any signer of the accounts, e.g.
+    /// made for another transaction or network
+    pub fn invalid_signatures(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+    ) -> Result<Vec<DecoratedSignature>> {
+        let txid = self.txid(network);
+        let mut signers = Vec::new();
+        for acc in accounts {
+            signers.extend(acc.signers.signers.iter().cloned());
+        }
+        Ok(self
+            .decorated_signatures()
             .iter()
-            .filter(|(pk, _)| signs.contains(&pk.get_signature_hint()))
+            .filter(|s| !signers.iter().any(|(key, _)| key.verify(&txid, s)))
             .cloned()
             .collect())
     }
 
-    pub fn is_published(&self) -> Result<bool> {
-        let res = horizon_mainnet().query_transaction(&self.txid(), FETCH_TIMEOUT)?;
-        Ok(res.successful)
+    /// Fail on invalid signatures that are not among the `known` ones
+    pub fn guard_invalid_signatures(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+        known: &[DecoratedSignature],
+    ) -> Result<()> {
+        for s in self.invalid_signatures(network, accounts)? {
+            if !known.contains(&s) {
+                return Err(MtlError::InvalidSignature(hex(&s.hint)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether every account collected the weight it needs
+    pub fn is_fully_signed(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+    ) -> Result<bool> {
+        for acc in accounts {
+            let collected: i32 = self
+                .get_signed_keys(network, &acc.signers)?
+                .iter()
+                .map(|(_, w)| w)
+                .sum();
+            if collected < acc.required_weight() as i32 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn signing_progress(
+        &self,
+        network: &NetworkConfig,
+        accounts: &[SigningAccount],
+    ) -> Result<SigningProgress> {
+        let mut progress = SigningProgress {
+            collected: 0,
+            required: 0,
+            awaiting: Vec::new(),
+        };
+        for (i, acc) in accounts.iter().enumerate() {
+            let signed = self.get_signed_keys(network, &acc.signers)?;
+            let collected: i32 = signed.iter().map(|(_, w)| w).sum();
+            let required = acc.required_weight() as i32;
+            if i == 0 {
+                progress.collected = collected;
+                progress.required = required;
+            }
+            if collected >= required {
+                continue;
+            }
+            for (key, weight) in acc.signers.signers.iter() {
+                if let Some(pk) = key.public_key() {
+                    if *weight > 0
+                        && !signed.iter().any(|(k, _)| k == key)
+                        && !progress.awaiting.contains(pk)
+                    {
+                        progress.awaiting.push(pk.clone());
+                    }
+                }
+            }
+        }
+        Ok(progress)
+    }
+
+    /// Send the envelope to the network once it has enough signatures
+    pub fn submit(&self, horizon: &dyn HorizonClient) -> Result<SubmitResult> {
+        self.guard_submit(horizon)?;
+        horizon.submit_transaction(self.envelope())
+    }
+
+    /// Check that the envelope can be sent, nothing is sent yet
+    pub fn guard_submit(&self, horizon: &dyn HorizonClient) -> Result<()> {
+        let accounts = self.fetch_signing_accounts(horizon)?;
+        if !self.is_fully_signed(horizon.network(), &accounts)? {
+            return Err(MtlError::NotEnoughSignatures);
+        }
+        Ok(())
+    }
+
+    pub fn is_published(&self, horizon: &dyn HorizonClient) -> Result<bool> {
+        horizon.query_transaction(&self.txid(horizon.network()))
+    }
+
+    /// Status the transaction has according to the network right now and
+    /// the reason if it can't be published anymore. Signers of the accounts
+    /// may have changed since the signatures were collected.
+    pub fn check_status(&self, horizon: &dyn HorizonClient) -> Result<(TxStatus, Option<String>)> {
+        let check = self.check_status_with_accounts(horizon)?;
+        Ok((check.status, check.reason))
+    }
+
+    /// Same as `check_status`, keeps the signing accounts the check fetched
+    pub fn check_status_with_accounts(&self, horizon: &dyn HorizonClient) -> Result<StatusCheck> {
+        let settled = |status| StatusCheck {
+            status,
+            reason: None,
+            accounts: None,
+        };
+        if self.is_published(horizon)? {
+            return Ok(settled(TxStatus::Published));
+        }
+        if self.is_expired() {
+            return Ok(settled(TxStatus::Expired));
+        }
+        if self.fetch_sequence_number(horizon)? > self.transaction().seq_num {
+            return Ok(settled(TxStatus::Superseded));
+        }
+        let accounts = self.fetch_signing_accounts(horizon)?;
+        let network = horizon.network();
+        let (status, reason) = match self
+            .guard_lock_out(&accounts)
+            .and_then(|_| self.guard_invalid_signatures(network, &accounts, &[]))
+        {
+            Err(e) => (TxStatus::Failed, Some(e.to_string())),
+            Ok(_) if self.is_fully_signed(network, &accounts)? => (TxStatus::Ready, None),
+            Ok(_) => (TxStatus::Collecting, None),
+        };
+        Ok(StatusCheck {
+            status,
+            reason,
+            accounts: Some(accounts),
+        })
     }
 
-    pub fn validate_update(&self, update: &Self) -> Result<()> {
-        if self.txid() != update.txid() {
+    pub fn validate_update(&self, horizon: &dyn HorizonClient, update: &Self) -> Result<()> {
+        let network = horizon.network();
+        if self.txid(network) != update.txid(network) {
             return Err(MtlError::UpdateContentChanged);
         }
-        let update_signs = update.signatures();
-        for s in self.signatures() {
-            if !update_signs.contains(&s) {
+        let update_signs = update.decorated_signatures();
+        for s in self.decorated_signatures() {
+            if !update_signs.contains(s) {
                 return Err(MtlError::UpdateSignatureRemoved);
             }
         }
-        let account = update.fetch_source_account()?;
-        update.guard_excess_signatures(&account)?;
+        let accounts = update.fetch_signing_accounts(horizon)?;
+        update.guard_invalid_signatures(network, &accounts, self.decorated_signatures())?;
+        update.guard_excess_signatures(network, &accounts)?;
+        Ok(())
+    }
+
+    /// Check the result of `merge_signatures` with the stored envelope. Unlike
+    /// `validate_update` it accepts losing stored signatures, the merge drops
+    /// the ones that stopped verifying or aren't needed after the account
+    /// signers changed.
+    pub fn validate_merge(&self, horizon: &dyn HorizonClient, merged: &Self) -> Result<()> {
+        let network = horizon.network();
+        if self.txid(network) != merged.txid(network) {
+            return Err(MtlError::UpdateContentChanged);
+        }
+        let accounts = merged.fetch_signing_accounts(horizon)?;
+        merged.guard_invalid_signatures(network, &accounts, &[])?;
+        merged.guard_excess_signatures(network, &accounts)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::horizon::MemoryHorizon;
+    use crate::registry::ManagedAccount;
+    use crate::signers::Thresholds;
+    use ed25519_dalek::{Keypair, SecretKey, Signer as _};
+    use serde_json::json;
+    use substrate_stellar_sdk::{
+        types::{
+            Asset, BumpSequenceOp, FeeBumpTransactionExt, Memo, Operation, OperationBody,
+            PaymentOp, SetOptionsOp, Signer, SignerKey, TimeBounds, TransactionExt,
+        },
+        IntoMuxedAccountId, IntoPublicKey,
+    };
+
+    const FOUNDATION: &str = "GDX23CPGMQ4LN55VGEDVFZPAJMAUEHSHAMJ2GMCU2ZSHN5QF4TMZYPIS";
+    const SIGNER1: &str = "GCFIRY65OQE7DFP5KLNS2PF2LVZMUZYJX4OZIEQ36N2IQANUB5XVYOJR";
+    const SIGNER2: &str = "GCATS5YOVB6ROX2WUNKGNQ2MP3GMXDMKSG2O4N5CLX3A6W4PZGZZI55U";
+    const SIGNER3: &str = "GDWUSKGGFDI4FRXK5EBTRECZSVQSSWJHHJOGH6JWG3AUMFFMQ435DIAG";
+    const ISSUER: &str = "GDFJHLAXAUMHA4OWPOB4P7YO72AQR2HMIUYFOXLXE2DZGM633K7HZDQP";
+    const SIGNER4: &str = "GBXHUHG5FGYLPD6RHL2MKWMP572O6KUXCZXDZJXS4T57ZTMAKBN7DWXN";
+    const FOREIGN: &str = "GDVEU3DD4KOFECV66VIHWEZOYX4ZKR3WV27L464SIIPOU2IUI3JCZA57";
+
+    fn account_json(
+        account: &str,
+        sequence: i64,
+        thresholds: (u8, u8, u8),
+        signers: &[(&str, i32)],
+    ) -> String {
+        let signers: Vec<serde_json::Value> = signers
+            .iter()
+            .map(|(key, weight)| json!({ "weight": weight, "key": key, "type": "ed25519_public_key" }))
+            .collect();
+        json!({
+            "id": account,
+            "account_id": account,
+            "sequence": sequence.to_string(),
+            "subentry_count": 0,
+            "last_modified_ledger": 1,
+            "last_modified_time": "2021-08-01T00:00:00Z",
+            "thresholds": {
+                "low_threshold": thresholds.0,
+                "med_threshold": thresholds.1,
+                "high_threshold": thresholds.2
+            },
+            "flags": {
+                "auth_required": false,
+                "auth_revocable": false,
+                "auth_immutable": false,
+                "auth_clawback_enabled": false
+            },
+            "balances": [{ "balance": "100.0000000", "buying_liabilities": "0.0000000", "selling_liabilities": "0.0000000", "asset_type": "native" }],
+            "signers": signers,
+            "data": {},
+            "num_sponsoring": 0,
+            "num_sponsored": 0,
+            "paging_token": account
+        })
+        .to_string()
+    }
+
+    fn registry() -> AccountsRegistry {
+        AccountsRegistry::new(vec![
+            ManagedAccount {
+                account: FOUNDATION.as_bytes().into_public_key().unwrap(),
+                label: "MTL Foundation".to_owned(),
+                description: String::new(),
+            },
+            ManagedAccount {
+                account: ISSUER.as_bytes().into_public_key().unwrap(),
+                label: "MTL issuer".to_owned(),
+                description: String::new(),
+            },
+        ])
+    }
+
+    /// Ledger with MTL foundation governed by three signers with thresholds 1/2/3
+    /// and MTL issuer that shares one of the signers
+    fn ledger() -> MemoryHorizon {
+        let horizon = MemoryHorizon::new(NetworkConfig::testnet());
+        horizon.insert_account(
+            FOUNDATION.as_bytes().into_public_key().unwrap(),
+            &account_json(
+                FOUNDATION,
+                100,
+                (1, 2, 3),
+                &[(SIGNER1, 1), (SIGNER2, 1), (SIGNER3, 1)],
+            ),
+        );
+        horizon.insert_account(
+            ISSUER.as_bytes().into_public_key().unwrap(),
+            &account_json(ISSUER, 100, (1, 1, 2), &[(SIGNER2, 1), (SIGNER4, 1)]),
+        );
+        horizon.insert_account(
+            FOREIGN.as_bytes().into_public_key().unwrap(),
+            &account_json(FOREIGN, 100, (0, 0, 0), &[(SIGNER1, 1)]),
+        );
+        horizon
+    }
+
+    fn parse(horizon: &MemoryHorizon, raw: &str) -> Result<MtlTransaction> {
+        parse_mtl_tx(horizon, &registry(), &raw)
+    }
+
+    fn envelope(
+        source: &str,
+        seq_num: i64,
+        time_bounds: Option<TimeBounds>,
+        signers: &[&str],
+    ) -> String {
+        let payment = OperationBody::Payment(PaymentOp {
+            destination: SIGNER1.as_bytes().into_muxed_account_id().unwrap(),
+            asset: Asset::AssetTypeNative,
+            amount: 10_000_000,
+        });
+        envelope_with(
+            source,
+            seq_num,
+            time_bounds,
+            vec![op(None, payment)],
+            signers,
+        )
+    }
+
+    fn op(source: Option<&str>, body: OperationBody) -> Operation {
+        Operation {
+            source_account: source.map(|s| s.as_bytes().into_muxed_account_id().unwrap()),
+            body,
+        }
+    }
+
+    fn envelope_with(
+        source: &str,
+        seq_num: i64,
+        time_bounds: Option<TimeBounds>,
+        operations: Vec<Operation>,
+        signers: &[&str],
+    ) -> String {
+        let source_key = source.as_bytes().into_public_key().unwrap();
+        let tx = Transaction {
+            source_account: source_key.into_muxed_account_id().unwrap(),
+            fee: MIN_FEE,
+            seq_num,
+            time_bounds,
+            memo: Memo::MemoNone,
+            operations: LimitedVarArray::new(operations).unwrap(),
+            ext: TransactionExt::V0,
+        };
+        let hash = hash_payload(
+            &NetworkConfig::testnet(),
+            TransactionSignaturePayloadTaggedTransaction::EnvelopeTypeTx(tx.clone()),
+        );
+        let envelope = TransactionEnvelope::EnvelopeTypeTx(TransactionV1Envelope {
+            tx,
+            signatures: signatures(&hash, signers),
+        });
+        std::str::from_utf8(&envelope.to_base64_xdr())
+            .unwrap()
+            .to_owned()
+    }
+
+    /// Fixture keys are derived from seeds of repeated bytes
+    fn keypair(key: &str) -> Keypair {
+        let seed = match key {
+            SIGNER1 => 1,
+            SIGNER2 => 2,
+            SIGNER3 => 3,
+            ISSUER => 4,
+            SIGNER4 => 5,
+            FOREIGN => 7,
+            _ => panic!("no secret for {}", key),
+        };
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    /// Ed25519 signers sign the hash, hash-x ones get zero bytes preimage
+    fn signature(hash: &[u8], signer: &str) -> DecoratedSignature {
+        let key = MtlSignerKey::from_encoding(signer).unwrap();
+        let signature = match key {
+            MtlSignerKey::Ed25519(_) => keypair(signer).sign(hash).to_bytes().to_vec(),
+            _ => vec![0; 64],
+        };
+        DecoratedSignature {
+            hint: key.signature_hint().unwrap(),
+            signature: LimitedVarOpaque::new(signature).unwrap(),
+        }
+    }
+
+    fn signatures(hash: &[u8], signers: &[&str]) -> LimitedVarArray<DecoratedSignature, 20> {
+        let signatures = signers.iter().map(|s| signature(hash, s)).collect();
+        LimitedVarArray::new(signatures).unwrap()
+    }
+
+    /// Append signature that has the signer hint but doesn't verify
+    fn forge_signature(raw: &str, signer: &str) -> String {
+        let mut envelope = match TransactionEnvelope::from_base64_xdr(raw).unwrap() {
+            TransactionEnvelope::EnvelopeTypeTx(envelope) => envelope,
+            _ => panic!("expected v1 transaction"),
+        };
+        let mut signatures = envelope.signatures.get_vec().clone();
+        signatures.push(signature(&[0; 32], signer));
+        envelope.signatures = LimitedVarArray::new(signatures).unwrap();
+        std::str::from_utf8(&TransactionEnvelope::EnvelopeTypeTx(envelope).to_base64_xdr())
+            .unwrap()
+            .to_owned()
+    }
+
+    /// Wrap already encoded transaction into fee bump paid by `fee_source`
+    fn fee_bump(fee_source: &str, inner: &str, fee: i64, signers: &[&str]) -> String {
+        let inner = match TransactionEnvelope::from_base64_xdr(inner).unwrap() {
+            TransactionEnvelope::EnvelopeTypeTx(envelope) => envelope,
+            _ => panic!("expected v1 transaction"),
+        };
+        let tx = FeeBumpTransaction {
+            fee_source: fee_source.as_bytes().into_muxed_account_id().unwrap(),
+            fee,
+            inner_tx: FeeBumpTransactionInnerTx::EnvelopeTypeTx(inner),
+            ext: FeeBumpTransactionExt::V0,
+        };
+        let hash = hash_payload(
+            &NetworkConfig::testnet(),
+            TransactionSignaturePayloadTaggedTransaction::EnvelopeTypeTxFeeBump(tx.clone()),
+        );
+        let envelope = TransactionEnvelope::EnvelopeTypeTxFeeBump(FeeBumpTransactionEnvelope {
+            tx,
+            signatures: signatures(&hash, signers),
+        });
+        std::str::from_utf8(&envelope.to_base64_xdr())
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn parse_accepts_foundation_tx() {
+        let horizon = ledger();
+        let raw = envelope(FOUNDATION, 101, None, &[SIGNER1]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert_eq!(tx.into_encoding(), raw);
+    }
+
+    #[test]
+    fn parse_rejects_foreign_account() {
+        let horizon = ledger();
+        let raw = envelope(FOREIGN, 101, None, &[]);
+        assert!(matches!(
+            parse(&horizon, &raw),
+            Err(MtlError::WrongSourceAccount)
+        ));
+    }
+
+    #[test]
+    fn validate_create_accepts_fresh_tx() {
+        let horizon = ledger();
+        let raw = envelope(FOUNDATION, 101, None, &[SIGNER1]);
+        assert!(validate_mtl_tx(&horizon, &registry(), &raw).is_ok());
+    }
+
+    #[test]
+    fn validate_create_rejects_overdue_sequence() {
+        let horizon = ledger();
+        let raw = envelope(FOUNDATION, 100, None, &[]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::SequenceNumber)
+        ));
+    }
+
+    #[test]
+    fn validate_create_rejects_short_time_window() {
+        let horizon = ledger();
+        let bounds = TimeBounds {
+            min_time: 0,
+            max_time: get_current_time() + 60,
+        };
+        let raw = envelope(FOUNDATION, 101, Some(bounds), &[]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::TooLittleTimeBound)
+        ));
+    }
+
+    #[test]
+    fn validate_update_accepts_new_signature() {
+        let horizon = ledger();
+        let old = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let new = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]),
+        )
+        .unwrap();
+        assert!(old.validate_update(&horizon, &new).is_ok());
+    }
+
+    #[test]
+    fn validate_update_rejects_removed_signature() {
+        let horizon = ledger();
+        let old = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let new = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER2])).unwrap();
+        assert!(matches!(
+            old.validate_update(&horizon, &new),
+            Err(MtlError::UpdateSignatureRemoved)
+        ));
+    }
+
+    #[test]
+    fn validate_update_rejects_changed_content() {
+        let horizon = ledger();
+        let old = parse(&horizon, &envelope(FOUNDATION, 101, None, &[])).unwrap();
+        let new = parse(&horizon, &envelope(FOUNDATION, 102, None, &[SIGNER1])).unwrap();
+        assert!(matches!(
+            old.validate_update(&horizon, &new),
+            Err(MtlError::UpdateContentChanged)
+        ));
+    }
+
+    #[test]
+    fn validate_update_rejects_excess_signatures() {
+        let horizon = ledger();
+        let old = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let new = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2, SIGNER3]),
+        )
+        .unwrap();
+        assert!(matches!(
+            old.validate_update(&horizon, &new),
+            Err(MtlError::SignaturesExcess)
+        ));
+    }
+
+    #[test]
+    fn trim_keeps_stored_signatures() {
+        let horizon = ledger();
+        let old = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER2])).unwrap();
+        let new = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2, SIGNER3]),
+        )
+        .unwrap();
+        let accounts = new.fetch_signing_accounts(&horizon).unwrap();
+        let trimmed = new
+            .trim_signatures(horizon.network(), &accounts, old.decorated_signatures())
+            .unwrap();
+        let signed: Vec<MtlSignerKey> = trimmed
+            .get_signed_keys(horizon.network(), &accounts[0].signers)
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(
+            signed,
+            vec![
+                MtlSignerKey::from_encoding(SIGNER1).unwrap(),
+                MtlSignerKey::from_encoding(SIGNER2).unwrap()
+            ]
+        );
+        assert!(old.validate_update(&horizon, &trimmed).is_ok());
+    }
+
+    #[test]
+    fn stored_signers_replace_horizon() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let fetched = tx.fetch_signing_accounts(&horizon).unwrap();
+        let stored = serde_json::to_string(&fetched[0].signers).unwrap();
+        let known = vec![(
+            fetched[0].account_id.clone(),
+            serde_json::from_str::<SignerSet>(&stored).unwrap(),
+        )];
+        let accounts = tx.known_signing_accounts(&known).unwrap();
+        assert_eq!(accounts[0].signers, fetched[0].signers);
+        assert_eq!(
+            tx.signing_progress(horizon.network(), &accounts).unwrap(),
+            tx.signing_progress(horizon.network(), &fetched).unwrap()
+        );
+        assert!(tx.known_signing_accounts(&[]).is_err());
+    }
+
+    #[test]
+    fn trim_drops_forged_signatures() {
+        let horizon = ledger();
+        let raw = forge_signature(&envelope(FOUNDATION, 101, None, &[SIGNER1]), SIGNER2);
+        let tx = parse(&horizon, &raw).unwrap();
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        let trimmed = tx
+            .trim_signatures(horizon.network(), &accounts, &[])
+            .unwrap();
+        assert_eq!(trimmed.decorated_signatures().len(), 1);
+        assert!(trimmed
+            .invalid_signatures(horizon.network(), &accounts)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_accepts_fee_bump() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOUNDATION, 101, None, &[SIGNER1]);
+        let raw = fee_bump(FOUNDATION, &inner_raw, 1000, &[SIGNER1]);
+        let tx = parse(&horizon, &raw).unwrap();
+        let inner = MtlTransaction::from_bytes(&tx.inner().unwrap().into_bytes()).unwrap();
+        let network = horizon.network();
+        assert!(tx.is_fee_bump());
+        assert_eq!(tx.into_encoding(), raw);
+        assert_eq!(tx.inner_txid(network), Some(inner.txid(network)));
+        assert_ne!(tx.txid(network), inner.txid(network));
+        assert_eq!(
+            tx.source_account().unwrap(),
+            FOUNDATION.as_bytes().into_public_key().unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_rejects_foreign_inner_tx() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOREIGN, 101, None, &[SIGNER1]);
+        let raw = fee_bump(FOUNDATION, &inner_raw, 1000, &[SIGNER1]);
+        assert!(matches!(
+            parse(&horizon, &raw),
+            Err(MtlError::WrongSourceAccount)
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_fee_bump_below_inner_fee() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]);
+        let raw = fee_bump(FOUNDATION, &inner_raw, MIN_FEE as i64, &[]);
+        assert!(matches!(
+            parse(&horizon, &raw),
+            Err(MtlError::NonStandardFee)
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_foreign_fee_source() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]);
+        let raw = fee_bump(FOREIGN, &inner_raw, 1000, &[]);
+        assert!(matches!(
+            parse(&horizon, &raw),
+            Err(MtlError::WrongSourceAccount)
+        ));
+    }
+
+    #[test]
+    fn fee_bump_requires_low_threshold() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]);
+        let signed = parse(
+            &horizon,
+            &fee_bump(FOUNDATION, &inner_raw, 1000, &[SIGNER1]),
+        )
+        .unwrap();
+        let accounts = signed.fetch_signing_accounts(&horizon).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].required_weight(), 1);
+        assert!(signed.validate_create(&horizon).is_ok());
+
+        let excess = parse(
+            &horizon,
+            &fee_bump(FOUNDATION, &inner_raw, 1000, &[SIGNER1, SIGNER2]),
+        )
+        .unwrap();
+        assert!(matches!(
+            excess.validate_create(&horizon),
+            Err(MtlError::SignaturesExcess)
+        ));
+    }
+
+    #[test]
+    fn fee_bump_requires_signed_inner_tx() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOUNDATION, 101, None, &[SIGNER1]);
+        let tx = parse(
+            &horizon,
+            &fee_bump(FOUNDATION, &inner_raw, 1000, &[SIGNER1]),
+        )
+        .unwrap();
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::NotEnoughSignatures)
+        ));
+
+        let forged = forge_signature(&envelope(FOUNDATION, 101, None, &[SIGNER1]), SIGNER2);
+        let tx = parse(&horizon, &fee_bump(FOUNDATION, &forged, 1000, &[SIGNER1])).unwrap();
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::InvalidSignature(_))
+        ));
+    }
+
+    fn set_options(master_weight: Option<u32>) -> OperationBody {
+        OperationBody::SetOptions(SetOptionsOp {
+            inflation_dest: None,
+            clear_flags: None,
+            set_flags: None,
+            master_weight,
+            low_threshold: None,
+            med_threshold: None,
+            high_threshold: None,
+            home_domain: None,
+            signer: None,
+        })
+    }
+
+    #[test]
+    fn payment_requires_medium_threshold() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[])).unwrap();
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        assert_eq!(
+            tx.required_threshold().unwrap(),
+            RequiredThreshold {
+                level: ThresholdLevel::Medium,
+                operation: Some((0, "payment")),
+            }
+        );
+        assert_eq!(accounts[0].required_weight(), 2);
+    }
+
+    #[test]
+    fn bump_sequence_requires_low_threshold() {
+        let horizon = ledger();
+        let bump = OperationBody::BumpSequence(BumpSequenceOp { bump_to: 200 });
+        let raw = envelope_with(FOUNDATION, 101, None, vec![op(None, bump)], &[SIGNER1]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert_eq!(tx.required_threshold().unwrap().level, ThresholdLevel::Low);
+        assert!(tx.validate_create(&horizon).is_ok());
+    }
+
+    #[test]
+    fn signer_change_drives_high_threshold() {
+        let horizon = ledger();
+        let ops = vec![op(None, set_options(None)), op(None, set_options(Some(0)))];
+        let raw = envelope_with(FOUNDATION, 101, None, ops, &[SIGNER1, SIGNER2, SIGNER3]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert_eq!(
+            tx.required_threshold().unwrap(),
+            RequiredThreshold {
+                level: ThresholdLevel::High,
+                operation: Some((1, "set_options")),
+            }
+        );
+        assert!(tx.validate_create(&horizon).is_ok());
+    }
+
+    #[test]
+    fn medium_threshold_rejects_third_signature() {
+        let horizon = ledger();
+        let raw = envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2, SIGNER3]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::SignaturesExcess)
+        ));
+    }
+
+    fn batch(signers: &[&str]) -> String {
+        let payment = |source| {
+            op(
+                source,
+                OperationBody::Payment(PaymentOp {
+                    destination: SIGNER1.as_bytes().into_muxed_account_id().unwrap(),
+                    asset: Asset::AssetTypeNative,
+                    amount: 10_000_000,
+                }),
+            )
+        };
+        let ops = vec![
+            payment(None),
+            payment(Some(ISSUER)),
+            op(Some(ISSUER), set_options(None)),
+        ];
+        envelope_with(FOUNDATION, 101, None, ops, signers)
+    }
+
+    #[test]
+    fn operation_sources_add_requirements() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &batch(&[])).unwrap();
+        let required = tx.required_thresholds().unwrap();
+        let threshold = |i, name| RequiredThreshold {
+            level: ThresholdLevel::Medium,
+            operation: Some((i, name)),
+        };
+        assert_eq!(
+            required,
+            vec![
+                (
+                    FOUNDATION.as_bytes().into_public_key().unwrap(),
+                    threshold(0, "payment")
+                ),
+                (
+                    ISSUER.as_bytes().into_public_key().unwrap(),
+                    threshold(1, "payment")
+                ),
+            ]
+        );
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        assert_eq!(
+            accounts
+                .iter()
+                .map(|a| a.required_weight())
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+    }
+
+    #[test]
+    fn shared_signer_covers_both_accounts() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &batch(&[SIGNER1, SIGNER2])).unwrap();
+        assert!(tx.validate_create(&horizon).is_ok());
+    }
+
+    #[test]
+    fn signature_no_account_needs_is_excess() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &batch(&[SIGNER1, SIGNER2, SIGNER4])).unwrap();
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::SignaturesExcess)
+        ));
+    }
+
+    fn set_signer(key: &str, weight: u32) -> OperationBody {
+        let key = match key.as_bytes().into_public_key().unwrap() {
+            PublicKey::PublicKeyTypeEd25519(k) => SignerKey::SignerKeyTypeEd25519(k),
+        };
+        OperationBody::SetOptions(SetOptionsOp {
+            signer: Some(Signer { key, weight }),
+            ..set_options_op()
+        })
+    }
+
+    fn set_options_op() -> SetOptionsOp {
+        match set_options(None) {
+            OperationBody::SetOptions(op) => op,
+            _ => unreachable!(),
+        }
+    }
+
+    fn governance(ops: Vec<OperationBody>) -> MtlTransaction {
+        let ops = ops.into_iter().map(|body| op(None, body)).collect();
+        let raw = envelope_with(FOUNDATION, 101, None, ops, &[SIGNER1, SIGNER2, SIGNER3]);
+        parse(&ledger(), &raw).unwrap()
+    }
+
+    #[test]
+    fn lock_out_rejects_removing_all_signers() {
+        let horizon = ledger();
+        let tx = governance(vec![
+            set_signer(SIGNER1, 0),
+            set_signer(SIGNER2, 0),
+            set_signer(SIGNER3, 0),
+        ]);
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::LockOut(account, _)) if account == FOUNDATION
+        ));
+    }
+
+    #[test]
+    fn lock_out_rejects_unreachable_threshold() {
+        let horizon = ledger();
+        let tx = governance(vec![OperationBody::SetOptions(SetOptionsOp {
+            high_threshold: Some(4),
+            ..set_options_op()
+        })]);
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::LockOut(_, _))
+        ));
+    }
+
+    #[test]
+    fn oversized_weight_is_rejected() {
+        let horizon = ledger();
+        let tx = governance(vec![set_signer(SIGNER4, 256)]);
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::WeightOverflow(256))
+        ));
+        let tx = governance(vec![OperationBody::SetOptions(SetOptionsOp {
+            low_threshold: Some(257),
+            ..set_options_op()
+        })]);
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::WeightOverflow(257))
+        ));
+    }
+
+    #[test]
+    fn fee_bump_simulates_inner_signers() {
+        let horizon = ledger();
+        let ops = vec![op(None, set_signer(SIGNER4, 2))];
+        let inner_raw = envelope_with(FOUNDATION, 101, None, ops, &[SIGNER1, SIGNER2]);
+        let tx = parse(&horizon, &fee_bump(FOUNDATION, &inner_raw, 1000, &[])).unwrap();
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        let signers = tx.simulate_signers(&accounts[0]).unwrap().unwrap();
+        assert_eq!(
+            signers.weight(&MtlSignerKey::from_encoding(SIGNER4).unwrap()),
+            2
+        );
+    }
+
+    #[test]
+    fn signer_rotation_keeps_account_usable() {
+        let horizon = ledger();
+        let tx = governance(vec![set_signer(SIGNER3, 0), set_signer(SIGNER4, 2)]);
+        assert!(tx.validate_create(&horizon).is_ok());
+
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        let signers = tx.simulate_signers(&accounts[0]).unwrap().unwrap();
+        let key = |k: &str| MtlSignerKey::from_encoding(k).unwrap();
+        assert_eq!(
+            signers.signers,
+            vec![(key(SIGNER1), 1), (key(SIGNER2), 1), (key(SIGNER4), 2)]
+        );
+        assert_eq!(signers.total_weight(), 4);
+
+        let before = accounts[0].signers.clone();
+        let changes: Vec<(MtlSignerKey, i32, i32)> = before
+            .diff(&signers)
+            .into_iter()
+            .map(|c| (c.key, c.before, c.after))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (key(SIGNER1), 1, 1),
+                (key(SIGNER2), 1, 1),
+                (key(SIGNER3), 1, 0),
+                (key(SIGNER4), 0, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn pre_auth_and_hash_x_signers_count_toward_threshold() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[])).unwrap();
+        let mut hash = [0; 32];
+        hash.copy_from_slice(&tx.txid(horizon.network()));
+        let pre_auth = MtlSignerKey::PreAuthTx(hash);
+        // Test signatures are zero bytes, so they are the preimage
+        hash.copy_from_slice(&Sha256::digest(&[0; 64]));
+        let hash_x = MtlSignerKey::HashX(hash);
+        horizon.insert_account(
+            FOUNDATION.as_bytes().into_public_key().unwrap(),
+            &account_json(
+                FOUNDATION,
+                100,
+                (1, 3, 3),
+                &[
+                    (SIGNER1, 1),
+                    (SIGNER2, 1),
+                    (&pre_auth.to_encoding(), 1),
+                    (&hash_x.to_encoding(), 1),
+                ],
+            ),
+        );
+        let account = get_account(&horizon, FOUNDATION).unwrap();
+        assert_eq!(get_mtl_signers(&account).unwrap().len(), 2);
+
+        let raw = envelope(FOUNDATION, 101, None, &[SIGNER1, &hash_x.to_encoding()]);
+        let tx = parse(&horizon, &raw).unwrap();
+        assert_eq!(
+            tx.get_signed_keys(
+                horizon.network(),
+                &SignerSet::from_account(&account).unwrap()
+            )
+            .unwrap(),
+            vec![
+                (MtlSignerKey::from_encoding(SIGNER1).unwrap(), 1),
+                (pre_auth, 1),
+                (hash_x, 1)
+            ]
+        );
+        assert!(tx.validate_create(&horizon).is_ok());
+    }
+
+    #[test]
+    fn pre_auth_of_inner_tx_does_not_sign_fee_bump() {
+        let horizon = ledger();
+        let inner_raw = envelope(FOUNDATION, 101, None, &[]);
+        let inner = parse(&horizon, &inner_raw).unwrap();
+        let mut hash = [0; 32];
+        hash.copy_from_slice(&inner.txid(horizon.network()));
+        let pre_auth = MtlSignerKey::PreAuthTx(hash);
+        let signers = SignerSet {
+            signers: vec![(pre_auth.clone(), 1)],
+            thresholds: Thresholds {
+                low: 1,
+                medium: 1,
+                high: 1,
+            },
+        };
+        assert_eq!(
+            inner.get_signed_keys(horizon.network(), &signers).unwrap(),
+            vec![(pre_auth, 1)]
+        );
+
+        let tx = parse(&horizon, &fee_bump(FOUNDATION, &inner_raw, 1000, &[])).unwrap();
+        assert!(tx
+            .get_signed_keys(horizon.network(), &signers)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn cancel_requires_author_or_signer() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[])).unwrap();
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        let key = |k: &str| k.as_bytes().into_public_key().unwrap();
+        assert!(!may_cancel(&accounts, None, None));
+        assert!(!may_cancel(&accounts, Some(SIGNER4), None));
+        assert!(!may_cancel(&accounts, None, Some(&key(SIGNER4))));
+        assert!(!may_cancel(&accounts, Some(SIGNER3), Some(&key(SIGNER4))));
+        assert!(may_cancel(&accounts, Some(SIGNER4), Some(&key(SIGNER4))));
+        assert!(may_cancel(&accounts, None, Some(&key(SIGNER1))));
+    }
+
+    #[test]
+    fn forged_signature_is_not_counted() {
+        let horizon = ledger();
+        let raw = forge_signature(&envelope(FOUNDATION, 101, None, &[SIGNER1]), SIGNER2);
+        let tx = parse(&horizon, &raw).unwrap();
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        let signed = tx
+            .get_signed_keys(horizon.network(), &accounts[0].signers)
+            .unwrap();
+        assert_eq!(
+            signed,
+            vec![(MtlSignerKey::from_encoding(SIGNER1).unwrap(), 1)]
+        );
+        let invalid = tx.invalid_signatures(horizon.network(), &accounts).unwrap();
+        assert_eq!(invalid.len(), 1);
+        assert!(matches!(
+            tx.validate_create(&horizon),
+            Err(MtlError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn validate_update_rejects_forged_signature() {
+        let horizon = ledger();
+        let raw = envelope(FOUNDATION, 101, None, &[SIGNER1]);
+        let old = parse(&horizon, &raw).unwrap();
+        let new = parse(&horizon, &forge_signature(&raw, SIGNER2)).unwrap();
+        assert!(matches!(
+            old.validate_update(&horizon, &new),
+            Err(MtlError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn merge_takes_union_of_signatures() {
+        let horizon = ledger();
+        let first = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let second = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER2, SIGNER1]),
+        )
+        .unwrap();
+        let third = parse(
+            &horizon,
+            &forge_signature(&envelope(FOUNDATION, 101, None, &[]), SIGNER3),
+        )
+        .unwrap();
+        let accounts = first.fetch_signing_accounts(&horizon).unwrap();
+        let merged = first
+            .merge_signatures(horizon.network(), &accounts, &[second, third])
+            .unwrap();
+        assert_eq!(
+            merged.signatures(),
+            vec![
+                SIGNER1
+                    .as_bytes()
+                    .into_public_key()
+                    .unwrap()
+                    .get_signature_hint(),
+                SIGNER2
+                    .as_bytes()
+                    .into_public_key()
+                    .unwrap()
+                    .get_signature_hint()
+            ]
+        );
+        assert!(merged.validate_create(&horizon).is_ok());
+        assert!(first.validate_update(&horizon, &merged).is_ok());
+    }
+
+    #[test]
+    fn merge_survives_removed_signer() {
+        let horizon = ledger();
+        let old = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        horizon.insert_account(
+            FOUNDATION.as_bytes().into_public_key().unwrap(),
+            &account_json(FOUNDATION, 100, (1, 2, 3), &[(SIGNER2, 1), (SIGNER3, 1)]),
+        );
+        let upload = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]),
+        )
+        .unwrap();
+        let network = horizon.network();
+        let accounts = upload.fetch_signing_accounts(&horizon).unwrap();
+        assert!(upload
+            .guard_invalid_signatures(network, &accounts, old.decorated_signatures())
+            .is_ok());
+        let merged = old
+            .merge_signatures(network, &accounts, &[upload])
+            .unwrap()
+            .trim_signatures(network, &accounts, old.decorated_signatures())
+            .unwrap();
+        assert_eq!(
+            merged.signatures(),
+            vec![SIGNER2
+                .as_bytes()
+                .into_public_key()
+                .unwrap()
+                .get_signature_hint()]
+        );
+        assert!(merged.validate_create(&horizon).is_ok());
+        assert!(old.validate_merge(&horizon, &merged).is_ok());
+        assert!(matches!(
+            old.validate_update(&horizon, &merged),
+            Err(MtlError::UpdateSignatureRemoved)
+        ));
+    }
+
+    #[test]
+    fn merge_rejects_other_transaction() {
+        let horizon = ledger();
+        let first = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let second = parse(&horizon, &envelope(FOUNDATION, 102, None, &[SIGNER2])).unwrap();
+        let accounts = first.fetch_signing_accounts(&horizon).unwrap();
+        assert!(matches!(
+            first.merge_signatures(horizon.network(), &accounts, &[second]),
+            Err(MtlError::UpdateContentChanged)
+        ));
+    }
+
+    #[test]
+    fn submit_requires_enough_signatures() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        assert!(matches!(
+            tx.submit(&horizon),
+            Err(MtlError::NotEnoughSignatures)
+        ));
+        assert!(horizon.submitted().is_empty());
+
+        let tx = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]),
+        )
+        .unwrap();
+        horizon.set_submit_result(SubmitResult::rejected("tx_bad_seq", &[]));
+        let result = tx.submit(&horizon).unwrap();
+        assert!(!result.successful);
+        assert_eq!(horizon.submitted(), vec![tx.into_bytes()]);
+    }
+
+    #[test]
+    fn status_follows_the_network() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        assert_eq!(
+            tx.check_status(&horizon).unwrap(),
+            (TxStatus::Collecting, None)
+        );
+
+        let tx = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]),
+        )
+        .unwrap();
+        assert_eq!(tx.check_status(&horizon).unwrap(), (TxStatus::Ready, None));
+
+        horizon.publish(&tx.txid(horizon.network()));
+        assert_eq!(
+            tx.check_status(&horizon).unwrap(),
+            (TxStatus::Published, None)
+        );
+
+        let tx = parse(&horizon, &envelope(FOUNDATION, 100, None, &[])).unwrap();
+        assert_eq!(
+            tx.check_status(&horizon).unwrap(),
+            (TxStatus::Superseded, None)
+        );
+    }
+
+    #[test]
+    fn status_check_keeps_accounts() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let check = tx.check_status_with_accounts(&horizon).unwrap();
+        assert_eq!(check.status, TxStatus::Collecting);
+        let accounts = check.accounts.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].signers.signers.len(), 3);
+
+        horizon.publish(&tx.txid(horizon.network()));
+        let check = tx.check_status_with_accounts(&horizon).unwrap();
+        assert_eq!(check.status, TxStatus::Published);
+        assert!(check.accounts.is_none());
+    }
+
+    #[test]
+    fn detached_signature_is_appended() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let txid = tx.txid(horizon.network());
+        let signer = SIGNER2.as_bytes().into_public_key().unwrap();
+        let raw = keypair(SIGNER2).sign(&txid).to_bytes();
+
+        let signature = raw.to_vec();
+        let signed = tx
+            .with_detached_signature(horizon.network(), &signer, &signature)
+            .unwrap();
+        let accounts = signed.fetch_signing_accounts(&horizon).unwrap();
+        let progress = signed
+            .signing_progress(horizon.network(), &accounts)
+            .unwrap();
+        assert_eq!(progress.collected, 2);
+        let again = signed
+            .with_detached_signature(horizon.network(), &signer, &signature)
+            .unwrap();
+        assert_eq!(again.decorated_signatures().len(), 2);
+
+        let other = SIGNER3.as_bytes().into_public_key().unwrap();
+        assert!(matches!(
+            tx.with_detached_signature(horizon.network(), &other, &signature),
+            Err(MtlError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn progress_lists_awaited_signers() {
+        let horizon = ledger();
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, None, &[SIGNER1])).unwrap();
+        let accounts = tx.fetch_signing_accounts(&horizon).unwrap();
+        let progress = tx.signing_progress(horizon.network(), &accounts).unwrap();
+        assert_eq!(progress.collected, 1);
+        assert_eq!(progress.required, 2);
+        let awaiting: Vec<PublicKey> = [SIGNER2, SIGNER3]
+            .iter()
+            .map(|k| k.as_bytes().into_public_key().unwrap())
+            .collect();
+        assert_eq!(progress.awaiting, awaiting);
+
+        let tx = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER3]),
+        )
+        .unwrap();
+        let progress = tx.signing_progress(horizon.network(), &accounts).unwrap();
+        assert_eq!(progress.collected, 2);
+        assert!(progress.awaiting.is_empty());
+    }
+
+    #[test]
+    fn status_fails_when_signers_change() {
+        let horizon = ledger();
+        let tx = parse(
+            &horizon,
+            &envelope(FOUNDATION, 101, None, &[SIGNER1, SIGNER2]),
+        )
+        .unwrap();
+        assert_eq!(tx.check_status(&horizon).unwrap(), (TxStatus::Ready, None));
+
+        horizon.insert_account(
+            FOUNDATION.as_bytes().into_public_key().unwrap(),
+            &account_json(
+                FOUNDATION,
+                100,
+                (1, 2, 3),
+                &[(SIGNER2, 1), (SIGNER3, 1), (SIGNER4, 1)],
+            ),
+        );
+        let (status, reason) = tx.check_status(&horizon).unwrap();
+        assert_eq!(status, TxStatus::Failed);
+        assert!(reason
+            .unwrap()
+            .contains("doesn't verify against any signer"));
+    }
+
+    #[test]
+    fn time_bounds_define_submission_time() {
+        let horizon = ledger();
+        let now = get_current_time();
+        let bounds = TimeBounds {
+            min_time: now + 3600,
+            max_time: now + 7200,
+        };
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, Some(bounds), &[])).unwrap();
+        assert_eq!(tx.valid_since(), now + 3600);
+        assert!(!tx.is_expired());
+
+        let bounds = TimeBounds {
+            min_time: 0,
+            max_time: now - 60,
+        };
+        let tx = parse(&horizon, &envelope(FOUNDATION, 101, Some(bounds), &[])).unwrap();
+        assert_eq!(tx.valid_since(), 0);
+        assert_eq!(tx.valid_until(), Some(now - 60));
+        assert!(tx.is_expired());
+    }
+}
@@ -0,0 +1 @@
+DROP TABLE submissions;
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE submissions (
+  id INTEGER NOT NULL PRIMARY KEY,
+  txid TEXT NOT NULL,
+  successful BOOLEAN NOT NULL,
+  ledger INTEGER,
+  fee_charged BIGINT,
+  result_codes TEXT NOT NULL,
+  submitted TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  FOREIGN KEY(txid) REFERENCES transactions(id)
+);
@@ -0,0 +1 @@
+ALTER TABLE transactions DROP COLUMN auto_submit;
\ No newline at end of file
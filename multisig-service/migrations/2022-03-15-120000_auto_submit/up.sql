@@ -0,0 +1 @@
+ALTER TABLE transactions ADD COLUMN auto_submit BOOLEAN NOT NULL DEFAULT 0;
\ No newline at end of file
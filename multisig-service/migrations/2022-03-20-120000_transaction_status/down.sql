@@ -0,0 +1,2 @@
+DROP TABLE status_changes;
+ALTER TABLE transactions DROP COLUMN status;
\ No newline at end of file
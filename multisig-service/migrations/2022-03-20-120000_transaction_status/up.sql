@@ -0,0 +1,9 @@
+ALTER TABLE transactions ADD COLUMN status TEXT NOT NULL DEFAULT 'collecting';
+CREATE TABLE status_changes (
+  id INTEGER NOT NULL PRIMARY KEY,
+  txid TEXT NOT NULL,
+  status TEXT NOT NULL,
+  reason TEXT,
+  changed TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  FOREIGN KEY(txid) REFERENCES transactions(id)
+);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE transactions DROP COLUMN checked;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE transactions ADD COLUMN checked TIMESTAMP;
\ No newline at end of file
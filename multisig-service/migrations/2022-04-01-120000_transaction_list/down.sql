@@ -0,0 +1,5 @@
+DROP TABLE awaited_signers;
+ALTER TABLE transactions DROP COLUMN required_weight;
+ALTER TABLE transactions DROP COLUMN collected_weight;
+ALTER TABLE transactions DROP COLUMN max_time;
+ALTER TABLE transactions DROP COLUMN source_account;
\ No newline at end of file
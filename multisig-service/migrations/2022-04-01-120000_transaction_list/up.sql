@@ -0,0 +1,11 @@
+ALTER TABLE transactions ADD COLUMN source_account TEXT;
+ALTER TABLE transactions ADD COLUMN max_time BIGINT;
+ALTER TABLE transactions ADD COLUMN collected_weight INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE transactions ADD COLUMN required_weight INTEGER NOT NULL DEFAULT 0;
+CREATE TABLE awaited_signers (
+  id INTEGER NOT NULL PRIMARY KEY,
+  txid TEXT NOT NULL,
+  signer TEXT NOT NULL,
+  FOREIGN KEY(txid) REFERENCES transactions(id)
+);
+CREATE INDEX awaited_signers_signer ON awaited_signers(signer);
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE transaction_updates DROP COLUMN author;
+ALTER TABLE transactions DROP COLUMN author;
+DROP TABLE sessions;
\ No newline at end of file
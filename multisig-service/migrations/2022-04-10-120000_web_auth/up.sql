@@ -0,0 +1,8 @@
+CREATE TABLE sessions (
+  token TEXT NOT NULL PRIMARY KEY,
+  signer TEXT NOT NULL,
+  created TIMESTAMP NOT NULL,
+  expires TIMESTAMP NOT NULL
+);
+ALTER TABLE transactions ADD COLUMN author TEXT;
+ALTER TABLE transaction_updates ADD COLUMN author TEXT;
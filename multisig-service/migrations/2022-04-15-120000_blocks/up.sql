@@ -0,0 +1,11 @@
+CREATE TABLE blocks (
+  id INTEGER NOT NULL PRIMARY KEY,
+  txid TEXT NOT NULL,
+  holder TEXT NOT NULL,
+  signer TEXT,
+  reason TEXT NOT NULL DEFAULT '',
+  created TIMESTAMP NOT NULL,
+  expires TIMESTAMP NOT NULL,
+  FOREIGN KEY(txid) REFERENCES transactions(id)
+);
+CREATE INDEX blocks_txid ON blocks(txid);
@@ -0,0 +1 @@
+ALTER TABLE transactions DROP COLUMN signers;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE transactions ADD COLUMN signers TEXT;
\ No newline at end of file
@@ -0,0 +1 @@
+-- Backfilled entries are indistinguishable from the recorded ones and stay.
@@ -0,0 +1,7 @@
+-- Transactions created before 2022-03-20-120000_transaction_status got the
+-- collecting status without a history entry. Record it as of their creation,
+-- the revalidation worker moves every unsettled row to its real status (for
+-- example published) on the first start after the upgrade.
+INSERT INTO status_changes (txid, status, reason, changed)
+SELECT id, status, NULL, created FROM transactions
+WHERE id NOT IN (SELECT txid FROM status_changes);
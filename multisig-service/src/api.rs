@@ -0,0 +1,503 @@
+//! Versioned JSON API for bots that drive the transaction lifecycle. Errors
+//! are reported with stable codes, see `ApiErrorCode`.
+
+use super::auth::{AuthError, Caller};
+use super::database::*;
+use super::{
+    add_signature, cancel, create, load_transaction_list, refresh_status, stored_signing_accounts,
+    submit, update, Cache, CancelError, CreateError, Horizon, NewTransaction, SignsMappingError,
+    SubmitError, TxHistoryItem, TxListQuery, UpdateError, ViewAccount, ViewError, ViewStatusChange,
+    ViewSubmission, ViewTxListItem,
+};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use montelibero_transactions::error::MtlError;
+use montelibero_transactions::horizon::HorizonClient;
+use montelibero_transactions::transaction::MtlTransaction;
+use rocket::http::Status;
+use rocket::response::{self, status::Created, Responder};
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{Request, Route, State};
+
+/// Error response body, `code` is stable and `message` is for humans
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiError {
+    #[serde(skip)]
+    status: Status,
+    code: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ApiErrorResp {
+    error: ApiError,
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        (self.status, Json(ApiErrorResp { error: self })).respond_to(req)
+    }
+}
+
+/// Errors that can be reported through the API
+pub trait ApiErrorCode: std::fmt::Display {
+    /// Machine readable code and HTTP status of the error
+    fn api_code(&self) -> (&'static str, Status);
+}
+
+impl<E: ApiErrorCode> From<E> for ApiError {
+    fn from(e: E) -> Self {
+        let (code, status) = e.api_code();
+        ApiError {
+            status,
+            code,
+            message: e.to_string(),
+        }
+    }
+}
+
+pub type ApiResult<T> = Result<Json<T>, ApiError>;
+
+impl ApiErrorCode for MtlError {
+    fn api_code(&self) -> (&'static str, Status) {
+        let status = if self.is_horizon_failure() {
+            Status::BadGateway
+        } else {
+            Status::UnprocessableEntity
+        };
+        (self.code(), status)
+    }
+}
+
+impl ApiErrorCode for DieselError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            DieselError::NotFound => ("not_found", Status::NotFound),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                ("already_exists", Status::Conflict)
+            }
+            _ => ("database", Status::InternalServerError),
+        }
+    }
+}
+
+impl ApiErrorCode for hex::FromHexError {
+    fn api_code(&self) -> (&'static str, Status) {
+        ("invalid_txid", Status::BadRequest)
+    }
+}
+
+impl ApiErrorCode for TxLoadError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            TxLoadError::Diesel(e) => e.api_code(),
+            TxLoadError::Transaction(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for SignsMappingError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            SignsMappingError::Mtl(e) => e.api_code(),
+            SignsMappingError::DatabaseError(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for CreateError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            CreateError::TransactionEmpty => ("transaction_empty", Status::BadRequest),
+            CreateError::TitleEmpty => ("title_empty", Status::BadRequest),
+            CreateError::MtlError(e) => e.api_code(),
+            CreateError::DatabaseError(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for UpdateError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            UpdateError::TransactionId(e) => e.api_code(),
+            UpdateError::TransactionLoad(e) => e.api_code(),
+            UpdateError::TransactionEmpty => ("transaction_empty", Status::BadRequest),
+            UpdateError::TransactionNotChanged => {
+                ("transaction_not_changed", Status::UnprocessableEntity)
+            }
+            UpdateError::MtlError(e) => e.api_code(),
+            UpdateError::DatabaseError(e) => e.api_code(),
+            UpdateError::SignsError(e) => e.api_code(),
+            UpdateError::Auth(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for AuthError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            AuthError::Disabled => ("auth_disabled", Status::NotFound),
+            AuthError::LoginRequired => ("login_required", Status::Unauthorized),
+            AuthError::NotSigner(_) => ("not_signer", Status::Forbidden),
+            AuthError::NotTxSigner(_) => ("not_tx_signer", Status::Forbidden),
+            AuthError::Mtl(e) => e.api_code(),
+            AuthError::DatabaseError(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for SubmitError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            SubmitError::InvalidTxid(e) => e.api_code(),
+            SubmitError::Mtl(e) => e.api_code(),
+            SubmitError::DatabaseError(e) => e.api_code(),
+            SubmitError::Diesel(e) => e.api_code(),
+            SubmitError::NotReady(_) => ("not_ready", Status::Conflict),
+            SubmitError::Auth(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for CancelError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            CancelError::InvalidTxid(e) => e.api_code(),
+            CancelError::DatabaseError(e) => e.api_code(),
+            CancelError::Diesel(e) => e.api_code(),
+            CancelError::Auth(e) => e.api_code(),
+            CancelError::Accounts(e) => e.api_code(),
+        }
+    }
+}
+
+impl ApiErrorCode for ViewError {
+    fn api_code(&self) -> (&'static str, Status) {
+        match self {
+            ViewError::InvalidTxid(e) => e.api_code(),
+            ViewError::UnknownOrder(_) => ("unknown_order", Status::UnprocessableEntity),
+            ViewError::InvalidAccount(_) => ("invalid_account", Status::UnprocessableEntity),
+            ViewError::Mtl(e) => e.api_code(),
+            ViewError::DatabaseError(e) => e.api_code(),
+            ViewError::Diesel(e) => e.api_code(),
+        }
+    }
+}
+
+/// Transaction with its signers, status and history
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiTransaction {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub source_account: String,
+    pub status: &'static str,
+    /// Why the transaction can't be published, if it can't
+    pub status_reason: Option<String>,
+    pub auto_submit: bool,
+    /// Signer key of the creator if they were logged in
+    pub author: Option<String>,
+    /// Current envelope in base64 XDR
+    pub body: String,
+    pub accounts: Vec<ViewAccount>,
+    pub history: Vec<TxHistoryItem>,
+    pub status_changes: Vec<ViewStatusChange>,
+    pub submissions: Vec<ViewSubmission>,
+}
+
+impl ApiTransaction {
+    async fn load(
+        conn: &TransactionsDb,
+        cache: &Cache,
+        horizon: &dyn HorizonClient,
+        txid: Vec<u8>,
+    ) -> Result<Self, ViewError> {
+        let network = horizon.network();
+        let tid = hex::encode(&txid);
+        let meta = get_transaction(conn, txid).await?;
+        let tx = meta.current().0;
+        let signing_accounts = stored_signing_accounts(conn, horizon, &meta, tid.clone()).await?;
+        let accounts = {
+            let registry = cache.registry.lock().await;
+            let signs_map = cache.signs.lock().await;
+            ViewAccount::collect(
+                &cache.users,
+                &signs_map,
+                &registry,
+                network,
+                &tx,
+                &signing_accounts,
+            )?
+        };
+        let status_changes = get_status_changes(conn, tid.clone()).await?;
+        let status_reason = status_changes.first().and_then(|c| c.reason.clone());
+        Ok(ApiTransaction {
+            source_account: std::str::from_utf8(&tx.source_account()?.to_encoding())
+                .unwrap()
+                .to_owned(),
+            status: meta.status.name(),
+            status_reason,
+            auto_submit: meta.auto_submit,
+            author: meta.authors.last().cloned().flatten(),
+            body: tx.into_encoding(),
+            accounts,
+            history: TxHistoryItem::collect(&meta),
+            status_changes: ViewStatusChange::collect(status_changes),
+            submissions: ViewSubmission::collect(get_submissions(conn, tid.clone()).await?),
+            id: tid,
+            title: meta.title,
+            description: meta.description,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiTransactionList {
+    pub transactions: Vec<ViewTxListItem>,
+    pub total: i64,
+    pub page: i64,
+    pub pages: i64,
+}
+
+/// Filters are the same as on the list page, `order` is one of the sortings
+#[get("/transactions?<status>&<source>&<awaiting>&<order>&<page>")]
+async fn list_transactions_page(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    status: Option<String>,
+    source: Option<String>,
+    awaiting: Option<String>,
+    order: Option<String>,
+    page: Option<i64>,
+) -> ApiResult<ApiTransactionList> {
+    let query = TxListQuery {
+        status,
+        source,
+        awaiting,
+        sort: order,
+        page,
+    };
+    let list = load_transaction_list(&conn, cache, &query).await?;
+    Ok(Json(ApiTransactionList {
+        transactions: list.transactions,
+        total: list.total,
+        page: list.page,
+        pages: list.pages,
+    }))
+}
+
+#[get("/transactions/<txid>")]
+async fn get_transaction_info(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    txid: String,
+) -> ApiResult<ApiTransaction> {
+    let txid = hex::decode(&txid)?;
+    let tx = ApiTransaction::load(&conn, cache, horizon.inner().as_ref(), txid).await?;
+    Ok(Json(tx))
+}
+
+#[post("/transactions", data = "<tx>")]
+async fn create_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    tx: Json<NewTransaction>,
+) -> Result<Created<Json<ApiTransaction>>, ApiError> {
+    let author = caller.author()?;
+    let horizon = horizon.inner();
+    let txid = create(&conn, cache, horizon, tx.into_inner(), author).await?;
+    let location = format!("/api/v1/transactions/{}", hex::encode(&txid));
+    let tx = ApiTransaction::load(&conn, cache, horizon, txid).await?;
+    Ok(Created::new(location).body(Json(tx)))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SignaturesUpload {
+    /// Envelope with new signatures in base64 XDR
+    pub body: String,
+}
+
+#[post("/transactions/<txid>/updates", data = "<tx>")]
+async fn update_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+    tx: Json<SignaturesUpload>,
+) -> ApiResult<ApiTransaction> {
+    let author = caller.author()?;
+    let horizon = horizon.inner();
+    let txid = hex::decode(&txid)?;
+    let upload = MtlTransaction::from_encoding(&tx.body)?;
+    if upload.txid(horizon.network()) != txid {
+        return Err(MtlError::UpdateContentChanged.into());
+    }
+    update(&conn, cache, horizon, &tx.body, author).await?;
+    Ok(Json(
+        ApiTransaction::load(&conn, cache, horizon, txid).await?,
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SignatureUpload {
+    /// Public key of the ed25519 signer
+    pub signer: String,
+    /// Signature of the transaction hash in hex or base64
+    pub signature: String,
+}
+
+#[post("/transactions/<txid>/signatures", data = "<sign>")]
+async fn sign_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+    sign: Json<SignatureUpload>,
+) -> ApiResult<ApiTransaction> {
+    let author = caller.author()?;
+    let horizon = horizon.inner();
+    add_signature(
+        &conn,
+        cache,
+        horizon,
+        &txid,
+        &sign.signer,
+        &sign.signature,
+        author,
+    )
+    .await?;
+    let txid = hex::decode(&txid)?;
+    Ok(Json(
+        ApiTransaction::load(&conn, cache, horizon, txid).await?,
+    ))
+}
+
+#[post("/transactions/<txid>/submit")]
+async fn submit_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+) -> ApiResult<ApiTransaction> {
+    let horizon = horizon.inner();
+    submit(&conn, horizon, &caller, &txid).await?;
+    let txid = hex::decode(&txid)?;
+    Ok(Json(
+        ApiTransaction::load(&conn, cache, horizon, txid).await?,
+    ))
+}
+
+#[post("/transactions/<txid>/cancel?<reason>")]
+async fn cancel_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+    reason: Option<String>,
+) -> ApiResult<ApiTransaction> {
+    let horizon = horizon.inner();
+    cancel(&conn, horizon, &caller, &txid, reason).await?;
+    let txid = hex::decode(&txid)?;
+    Ok(Json(
+        ApiTransaction::load(&conn, cache, horizon, txid).await?,
+    ))
+}
+
+/// Re-check the transaction against the network right away
+#[post("/transactions/<txid>/refresh")]
+async fn refresh_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+) -> ApiResult<ApiTransaction> {
+    caller.authorize()?;
+    let horizon = horizon.inner();
+    let txid = hex::decode(&txid)?;
+    refresh_status(&conn, horizon, txid.clone()).await?;
+    Ok(Json(
+        ApiTransaction::load(&conn, cache, horizon, txid).await?,
+    ))
+}
+
+#[catch(400)]
+fn bad_request() -> ApiError {
+    ApiError {
+        status: Status::BadRequest,
+        code: "bad_request",
+        message: "Request is malformed".to_owned(),
+    }
+}
+
+#[catch(404)]
+fn not_found() -> ApiError {
+    ApiError {
+        status: Status::NotFound,
+        code: "not_found",
+        message: "Unknown API endpoint".to_owned(),
+    }
+}
+
+#[catch(422)]
+fn unprocessable() -> ApiError {
+    ApiError {
+        status: Status::UnprocessableEntity,
+        code: "malformed_request",
+        message: "Request body doesn't match the expected JSON".to_owned(),
+    }
+}
+
+#[catch(500)]
+fn internal_error() -> ApiError {
+    ApiError {
+        status: Status::InternalServerError,
+        code: "internal_error",
+        message: "Internal server error".to_owned(),
+    }
+}
+
+/// Any other failure, keeps the status Rocket reported
+#[catch(default)]
+fn default_catcher(status: Status, _req: &Request) -> ApiError {
+    ApiError {
+        status,
+        code: "http_error",
+        message: status.reason().unwrap_or("Request failed").to_owned(),
+    }
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        list_transactions_page,
+        get_transaction_info,
+        create_transaction,
+        update_transaction,
+        sign_transaction,
+        submit_transaction,
+        cancel_transaction,
+        refresh_transaction,
+    ]
+}
+
+pub fn catchers() -> Vec<rocket::Catcher> {
+    catchers![
+        bad_request,
+        not_found,
+        unprocessable,
+        internal_error,
+        default_catcher
+    ]
+}
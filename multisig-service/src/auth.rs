@@ -0,0 +1,346 @@
+//! Signer login with Stellar web authentication (SEP-10). Sessions let the
+//! service attribute transactions, updates and blocks to signer keys.
+
+use super::api::ApiErrorCode;
+use super::database::*;
+use super::{off_runtime, Cache, Horizon};
+use chrono::{Duration, Utc};
+use montelibero_transactions::account::get_mtl_signers;
+use montelibero_transactions::auth::WebAuth;
+use montelibero_transactions::error::MtlError;
+use montelibero_transactions::horizon::HorizonClient;
+use montelibero_transactions::transaction::MtlTransaction;
+use rand::RngCore;
+use rocket::http::{Cookie, CookieJar, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::Redirect;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{Route, State};
+use rocket_dyn_templates::{context, Template};
+use substrate_stellar_sdk::PublicKey;
+use thiserror::Error;
+
+/// Private cookie that holds the session token for browsers
+const SESSION_COOKIE: &str = "session";
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthConfig {
+    /// Seed of the key challenges are signed with
+    secret: String,
+    home_domain: String,
+    /// Defaults to the home domain
+    web_auth_domain: Option<String>,
+    session_hours: Option<i64>,
+    /// Only logged in signers can create, update and block transactions
+    #[serde(default)]
+    signers_only: bool,
+}
+
+/// Web authentication settings, disabled if the config has no `auth` section
+pub struct Auth {
+    web_auth: Option<WebAuth>,
+    session_duration: Duration,
+    signers_only: bool,
+}
+
+impl Auth {
+    pub fn new(config: Option<AuthConfig>) -> Result<Self, MtlError> {
+        let config = match config {
+            None => {
+                return Ok(Auth {
+                    web_auth: None,
+                    session_duration: Duration::hours(24),
+                    signers_only: false,
+                })
+            }
+            Some(config) => config,
+        };
+        let home_domain = config.home_domain;
+        let web_auth_domain = config
+            .web_auth_domain
+            .unwrap_or_else(|| home_domain.clone());
+        Ok(Auth {
+            web_auth: Some(WebAuth::new(&config.secret, home_domain, web_auth_domain)?),
+            session_duration: Duration::hours(config.session_hours.unwrap_or(24)),
+            signers_only: config.signers_only,
+        })
+    }
+
+    fn web_auth(&self) -> Result<&WebAuth, AuthError> {
+        self.web_auth.as_ref().ok_or(AuthError::Disabled)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Web authentication is not configured")]
+    Disabled,
+    #[error("Log in with your signer key first")]
+    LoginRequired,
+    #[error("{0} is not a signer of the managed accounts")]
+    NotSigner(String),
+    #[error("{0} is neither a signer nor the author of the transaction")]
+    NotTxSigner(String),
+    #[error("{0}")]
+    Mtl(#[from] MtlError),
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] diesel::result::Error),
+}
+
+/// Signer with a live session, taken from the `Authorization: Bearer` header
+/// or the session cookie
+pub struct Signer {
+    pub key: PublicKey,
+    token: String,
+}
+
+impl Signer {
+    pub fn encoding(&self) -> String {
+        std::str::from_utf8(&self.key.to_encoding())
+            .unwrap()
+            .to_owned()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Signer {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        let token = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(|t| t.to_owned())
+            .or_else(|| {
+                req.cookies()
+                    .get_private(SESSION_COOKIE)
+                    .map(|c| c.value().to_owned())
+            });
+        let token = match token {
+            Some(token) => token,
+            None => return Outcome::Forward(()),
+        };
+        let conn = match req.guard::<TransactionsDb>().await {
+            Outcome::Success(conn) => conn,
+            _ => return Outcome::Forward(()),
+        };
+        match get_session(&conn, token.clone()).await {
+            Ok(Some(session)) => match PublicKey::from_encoding(&session.signer) {
+                Ok(key) => Outcome::Success(Signer { key, token }),
+                Err(_) => Outcome::Forward(()),
+            },
+            _ => Outcome::Forward(()),
+        }
+    }
+}
+
+/// Caller of an action that is attributed to the logged in signer
+pub struct Caller {
+    pub signer: Option<Signer>,
+    signers_only: bool,
+}
+
+impl Caller {
+    pub fn key(&self) -> Option<String> {
+        self.signer.as_ref().map(|s| s.encoding())
+    }
+
+    /// Signer key to record for the action, fails if the service requires
+    /// login and there is none
+    pub fn author(&self) -> Result<Option<String>, AuthError> {
+        match self.key() {
+            None if self.signers_only => Err(AuthError::LoginRequired),
+            key => Ok(key),
+        }
+    }
+
+    /// Fails if the service requires login and there is none
+    pub fn authorize(&self) -> Result<(), AuthError> {
+        self.author().map(|_| ())
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Caller {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        let signers_only = matches!(req.rocket().state::<Auth>(), Some(a) if a.signers_only);
+        let signer = req.guard::<Signer>().await.succeeded();
+        Outcome::Success(Caller {
+            signer,
+            signers_only,
+        })
+    }
+}
+
+/// Whether the key is an ed25519 signer of any of the managed accounts
+async fn is_managed_signer(
+    cache: &Cache,
+    horizon: &Horizon,
+    key: PublicKey,
+) -> Result<bool, MtlError> {
+    let accounts: Vec<_> = {
+        let registry = cache.registry.lock().await;
+        registry
+            .accounts()
+            .iter()
+            .map(|a| a.account.clone())
+            .collect()
+    };
+    off_runtime(horizon, move |horizon| {
+        for account in accounts {
+            let account = horizon.fetch_account(&account)?;
+            if get_mtl_signers(&account)?.iter().any(|(k, _)| *k == key) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    })
+    .await
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct AuthErrorResp {
+    error: String,
+}
+
+type AuthResult<T> = Result<Json<T>, (Status, Json<AuthErrorResp>)>;
+
+fn auth_error(e: AuthError) -> (Status, Json<AuthErrorResp>) {
+    let (_, status) = e.api_code();
+    (
+        status,
+        Json(AuthErrorResp {
+            error: e.to_string(),
+        }),
+    )
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ChallengeResp {
+    transaction: String,
+    network_passphrase: String,
+}
+
+#[get("/auth?<account>")]
+fn get_challenge(
+    auth: &State<Auth>,
+    horizon: &State<Horizon>,
+    account: String,
+) -> AuthResult<ChallengeResp> {
+    fn challenge(
+        auth: &Auth,
+        horizon: &dyn HorizonClient,
+        account: &str,
+    ) -> Result<MtlTransaction, AuthError> {
+        let client = PublicKey::from_encoding(account).map_err(MtlError::from)?;
+        let mut nonce = [0; 48];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let now = Utc::now().timestamp() as u64;
+        Ok(auth
+            .web_auth()?
+            .challenge(horizon.network(), &client, &nonce, now)?)
+    }
+
+    let tx = challenge(auth, horizon.inner().as_ref(), &account).map_err(auth_error)?;
+    Ok(Json(ChallengeResp {
+        transaction: tx.into_encoding(),
+        network_passphrase: horizon.network().passphrase.clone(),
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct SignedChallenge {
+    transaction: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct TokenResp {
+    token: String,
+    signer: String,
+    expires: String,
+}
+
+#[post("/auth", data = "<challenge>")]
+async fn post_challenge(
+    conn: TransactionsDb,
+    auth: &State<Auth>,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    cookies: &CookieJar<'_>,
+    challenge: Json<SignedChallenge>,
+) -> AuthResult<TokenResp> {
+    async fn login(
+        conn: &TransactionsDb,
+        auth: &Auth,
+        cache: &Cache,
+        horizon: &Horizon,
+        challenge: &str,
+    ) -> Result<Session, AuthError> {
+        let challenge = MtlTransaction::from_encoding(&challenge)?;
+        let now = Utc::now();
+        let key = auth
+            .web_auth()?
+            .verify(horizon.network(), &challenge, now.timestamp() as u64)?;
+        let signer = std::str::from_utf8(&key.to_encoding()).unwrap().to_owned();
+        if !is_managed_signer(cache, horizon, key).await? {
+            return Err(AuthError::NotSigner(signer));
+        }
+        let mut token = [0; 32];
+        rand::thread_rng().fill_bytes(&mut token);
+        let session = Session {
+            token: hex::encode(token),
+            signer,
+            created: now.naive_utc(),
+            expires: (now + auth.session_duration).naive_utc(),
+        };
+        create_session(conn, session.clone()).await?;
+        Ok(session)
+    }
+
+    let session = login(&conn, auth, cache, horizon.inner(), &challenge.transaction)
+        .await
+        .map_err(auth_error)?;
+    cookies.add_private(Cookie::new(SESSION_COOKIE, session.token.clone()));
+    Ok(Json(TokenResp {
+        token: session.token,
+        signer: session.signer,
+        expires: session.expires.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }))
+}
+
+#[get("/login")]
+fn login_page(auth: &State<Auth>, signer: Option<Signer>) -> Template {
+    Template::render(
+        "login",
+        &context! {
+            title: "Montelibero multisignature service",
+            parent: "base",
+            menu_login: true,
+            is_enabled: auth.web_auth.is_some(),
+            signer: signer.map(|s| s.encoding()),
+        },
+    )
+}
+
+#[post("/logout")]
+async fn logout(conn: TransactionsDb, cookies: &CookieJar<'_>, signer: Option<Signer>) -> Redirect {
+    if let Some(signer) = signer {
+        if let Err(e) = delete_session(&conn, signer.token).await {
+            warn_!("Failed to delete session: {}", e);
+        }
+    }
+    cookies.remove_private(Cookie::named(SESSION_COOKIE));
+    Redirect::to(uri!(login_page()))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![get_challenge, post_challenge, login_page, logout]
+}
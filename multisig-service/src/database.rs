@@ -1,8 +1,15 @@
 use chrono::NaiveDateTime;
-use diesel::{self, prelude::*, result::QueryResult};
+use diesel::{self, prelude::*, result::QueryResult, sqlite::Sqlite};
 use montelibero_transactions::error::MtlError;
-use montelibero_transactions::transaction::MtlTransaction;
-use rocket::serde::Serialize;
+use montelibero_transactions::network::NetworkConfig;
+use montelibero_transactions::signers::SignerSet;
+use montelibero_transactions::status::TxStatus;
+use montelibero_transactions::submit::SubmitResult;
+use montelibero_transactions::transaction::{MtlTransaction, SigningAccount, SigningProgress};
+use rocket::serde::json::serde_json;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use substrate_stellar_sdk::{AccountId, PublicKey};
 use thiserror::Error;
 
 #[database("transactions")]
@@ -21,6 +28,37 @@ pub struct Transaction {
     pub description: String,
     pub body: Vec<u8>,
     pub created: NaiveDateTime,
+    /// Transaction wrapped by the fee bump
+    pub inner_txid: Option<String>,
+    /// Submit the transaction as soon as it collects enough signatures
+    pub auto_submit: bool,
+    /// Name of the current `TxStatus`
+    pub status: String,
+    /// Last time the status was checked against the network
+    pub checked: Option<NaiveDateTime>,
+    /// Account that authorizes the envelope
+    pub source_account: Option<String>,
+    pub max_time: Option<i64>,
+    pub collected_weight: i32,
+    pub required_weight: i32,
+    /// Signer key of the authenticated creator
+    pub author: Option<String>,
+    /// JSON list of `AccountSigners` as of the last check
+    pub signers: Option<String>,
+}
+
+/// Transaction without its body for listing
+#[derive(Serialize, Queryable, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct TransactionListItem {
+    pub id: String,
+    pub title: String,
+    pub source_account: Option<String>,
+    pub status: String,
+    pub collected_weight: i32,
+    pub required_weight: i32,
+    pub created: NaiveDateTime,
+    pub max_time: Option<i64>,
 }
 
 #[derive(Serialize, Queryable, Insertable, Debug, Clone)]
@@ -31,6 +69,8 @@ pub struct TransactionUpdate {
     pub txid: String,
     pub body: Vec<u8>,
     pub updated: NaiveDateTime,
+    /// Signer key of the authenticated uploader
+    pub author: Option<String>,
 }
 
 #[derive(Serialize, Queryable, Insertable, Debug, Clone)]
@@ -40,25 +80,105 @@ pub struct TransactionUpdateCreate {
     pub txid: String,
     pub body: Vec<u8>,
     pub updated: NaiveDateTime,
+    /// Signer key of the authenticated uploader
+    pub author: Option<String>,
+}
+
+/// Result of sending the transaction to Horizon
+#[derive(Serialize, Queryable, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct Submission {
+    pub id: i32,
+    pub txid: String,
+    pub successful: bool,
+    pub ledger: Option<i32>,
+    pub fee_charged: Option<i64>,
+    /// Decoded result codes, one per line
+    pub result_codes: String,
+    pub submitted: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "submissions"]
+pub struct SubmissionCreate {
+    pub txid: String,
+    pub successful: bool,
+    pub ledger: Option<i32>,
+    pub fee_charged: Option<i64>,
+    pub result_codes: String,
+    pub submitted: NaiveDateTime,
+}
+
+/// Moment the transaction entered the status
+#[derive(Serialize, Queryable, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct StatusChange {
+    pub id: i32,
+    pub txid: String,
+    pub status: String,
+    /// Why the transaction can't be published, if it can't
+    pub reason: Option<String>,
+    pub changed: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "status_changes"]
+pub struct StatusChangeCreate {
+    pub txid: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub changed: NaiveDateTime,
 }
 
 pub async fn store_transaction(
     conn: &TransactionsDb,
+    network: &NetworkConfig,
     tx: MtlTransaction,
     title: String,
     description: String,
+    auto_submit: bool,
+    author: Option<String>,
 ) -> QueryResult<()> {
+    let id = hex::encode(tx.txid(network));
+    let inner_txid = tx.inner_txid(network).map(hex::encode);
+    let source_account = tx
+        .source_account()
+        .ok()
+        .map(|a| String::from_utf8_lossy(&a.to_encoding()).into_owned());
+    let max_time = tx.valid_until().map(|t| t as i64);
     conn.run(move |c| {
-        let t = Transaction {
-            id: hex::encode(tx.txid()),
-            title,
-            description,
-            body: tx.into_bytes(),
-            created: chrono::Utc::now().naive_utc(),
-        };
-        diesel::insert_into(transactions::table)
-            .values(&t)
-            .execute(c)
+        c.transaction(|| {
+            let created = chrono::Utc::now().naive_utc();
+            let t = Transaction {
+                id: id.clone(),
+                title,
+                description,
+                body: tx.into_bytes(),
+                created,
+                inner_txid,
+                auto_submit,
+                status: TxStatus::Collecting.name().to_owned(),
+                checked: None,
+                source_account,
+                max_time,
+                collected_weight: 0,
+                required_weight: 0,
+                author,
+                signers: None,
+            };
+            diesel::insert_into(transactions::table)
+                .values(&t)
+                .execute(c)?;
+            let change = StatusChangeCreate {
+                txid: id,
+                status: t.status,
+                reason: None,
+                changed: created,
+            };
+            diesel::insert_into(status_changes::table)
+                .values(&change)
+                .execute(c)
+        })
     })
     .await?;
     Ok(())
@@ -66,13 +186,17 @@ pub async fn store_transaction(
 
 pub async fn store_transaction_update(
     conn: &TransactionsDb,
+    network: &NetworkConfig,
     tx: MtlTransaction,
+    author: Option<String>,
 ) -> QueryResult<()> {
+    let txid = hex::encode(tx.txid(network));
     conn.run(move |c| {
         let t = TransactionUpdateCreate {
-            txid: hex::encode(tx.txid()),
+            txid,
             body: tx.into_bytes(),
             updated: chrono::Utc::now().naive_utc(),
+            author,
         };
         diesel::insert_into(transaction_updates::table)
             .values(&t)
@@ -85,7 +209,15 @@ pub async fn store_transaction_update(
 pub struct MtlTxMeta {
     pub title: String,
     pub description: String,
+    pub inner_txid: Option<String>,
+    pub auto_submit: bool,
+    pub status: TxStatus,
+    pub checked: Option<NaiveDateTime>,
     pub history: Vec<(MtlTransaction, NaiveDateTime)>,
+    /// Who uploaded each of the `history` versions, if known
+    pub authors: Vec<Option<String>>,
+    /// Signers of the accounts the transaction needs as of the last check
+    pub signers: Option<Vec<(AccountId, SignerSet)>>,
 }
 
 impl MtlTxMeta {
@@ -94,6 +226,34 @@ impl MtlTxMeta {
     }
 }
 
+/// Signers of one of the accounts the transaction needs
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct AccountSigners {
+    account: String,
+    signers: SignerSet,
+}
+
+fn encode_signers(accounts: &[SigningAccount]) -> String {
+    let stored: Vec<AccountSigners> = accounts
+        .iter()
+        .map(|acc| AccountSigners {
+            account: String::from_utf8_lossy(&acc.account_id.to_encoding()).into_owned(),
+            signers: acc.signers.clone(),
+        })
+        .collect();
+    serde_json::to_string(&stored).unwrap()
+}
+
+/// Unreadable snapshots are treated as missing, the next check replaces them
+fn decode_signers(stored: Option<&str>) -> Option<Vec<(AccountId, SignerSet)>> {
+    let stored: Vec<AccountSigners> = serde_json::from_str(stored?).ok()?;
+    stored
+        .into_iter()
+        .map(|s| Some((PublicKey::from_encoding(&s.account).ok()?, s.signers)))
+        .collect()
+}
+
 #[derive(Debug, Error)]
 pub enum TxLoadError {
     #[error("Failed to load tx due Database error: {0}")]
@@ -117,23 +277,78 @@ pub async fn get_transaction(
             .load::<TransactionUpdate>(c)?;
 
         let mut history: Vec<(MtlTransaction, NaiveDateTime)> = Vec::new();
+        let mut authors = Vec::new();
         for u in updates.iter() {
             history.push((MtlTransaction::from_bytes(&u.body)?, u.updated));
+            authors.push(u.author.clone());
         }
         history.push((
             MtlTransaction::from_bytes(&tx_created.body)?,
             tx_created.created,
         ));
+        authors.push(tx_created.author);
 
         Ok(MtlTxMeta {
             title: tx_created.title,
             description: tx_created.description,
+            inner_txid: tx_created.inner_txid,
+            auto_submit: tx_created.auto_submit,
+            status: TxStatus::from_name(&tx_created.status)?,
+            checked: tx_created.checked,
             history,
+            authors,
+            signers: decode_signers(tx_created.signers.as_deref()),
         })
     })
     .await
 }
 
+/// Attach the last signed version to each of the transactions
+fn load_current_versions(
+    c: &diesel::SqliteConnection,
+    txs: Vec<Transaction>,
+) -> Result<Vec<MtlTxMeta>, TxLoadError> {
+    let ids: Vec<String> = txs.iter().map(|tx| tx.id.clone()).collect();
+    let mut last_updates: HashMap<String, i32> = HashMap::new();
+    for (txid, id) in all_transaction_updates
+        .select((transaction_updates::txid, transaction_updates::id))
+        .filter(transaction_updates::txid.eq_any(&ids))
+        .order(transaction_updates::updated.asc())
+        .load::<(String, i32)>(c)?
+    {
+        last_updates.insert(txid, id);
+    }
+    let mut updates: HashMap<String, TransactionUpdate> = all_transaction_updates
+        .filter(transaction_updates::id.eq_any(last_updates.values()))
+        .load::<TransactionUpdate>(c)?
+        .into_iter()
+        .map(|u| (u.txid.clone(), u))
+        .collect();
+
+    let mut result = vec![];
+    for tx in txs {
+        let (current, author) = match updates.remove(&tx.id) {
+            Some(u) => ((MtlTransaction::from_bytes(&u.body)?, u.updated), u.author),
+            None => (
+                (MtlTransaction::from_bytes(&tx.body)?, tx.created),
+                tx.author,
+            ),
+        };
+        result.push(MtlTxMeta {
+            title: tx.title,
+            description: tx.description,
+            inner_txid: tx.inner_txid,
+            auto_submit: tx.auto_submit,
+            status: TxStatus::from_name(&tx.status)?,
+            checked: tx.checked,
+            history: vec![current],
+            authors: vec![author],
+            signers: decode_signers(tx.signers.as_deref()),
+        });
+    }
+    Ok(result)
+}
+
 /// Loads all transaction from given time. Loads only last signed version.
 pub async fn get_transactions(
     conn: &TransactionsDb,
@@ -143,29 +358,460 @@ pub async fn get_transactions(
         let txs = all_transactions
             .filter(transactions::created.gt(from))
             .get_results::<Transaction>(c)?;
+        load_current_versions(c, txs)
+    })
+    .await
+}
 
-        let mut result = vec![];
-        for tx in txs {
-            let updates = all_transaction_updates
-                .order(transaction_updates::updated.desc())
-                .filter(transaction_updates::txid.eq(tx.id))
-                .limit(1)
-                .get_results::<TransactionUpdate>(c)?;
-
-            let mut history = vec![];
-            for u in updates {
-                history.push((MtlTransaction::from_bytes(&u.body)?, u.updated));
-            }
-            history.push((MtlTransaction::from_bytes(&tx.body)?, tx.created));
+/// Order of the transactions list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TxOrder {
+    #[default]
+    Newest,
+    Oldest,
+    /// Transactions that expire sooner go first, ones without time bounds last
+    Expiring,
+    Title,
+}
+
+pub static ALL_ORDERS: [TxOrder; 4] = [
+    TxOrder::Newest,
+    TxOrder::Oldest,
+    TxOrder::Expiring,
+    TxOrder::Title,
+];
+
+impl TxOrder {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxOrder::Newest => "newest",
+            TxOrder::Oldest => "oldest",
+            TxOrder::Expiring => "expiring",
+            TxOrder::Title => "title",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ALL_ORDERS.iter().find(|o| o.name() == name).copied()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TxFilter {
+    pub status: Option<TxStatus>,
+    pub source_account: Option<String>,
+    /// Signer key the transactions are waiting for
+    pub awaiting: Option<String>,
+}
+
+impl TxFilter {
+    fn query(&self) -> transactions::BoxedQuery<'static, Sqlite> {
+        let mut query = transactions::table.into_boxed();
+        if let Some(status) = self.status {
+            query = query.filter(transactions::status.eq(status.name()));
+        }
+        if let Some(source) = &self.source_account {
+            query = query.filter(transactions::source_account.eq(source.clone()));
+        }
+        if let Some(signer) = &self.awaiting {
+            let awaited = awaited_signers::table
+                .select(awaited_signers::txid)
+                .filter(awaited_signers::signer.eq(signer.clone()));
+            query = query.filter(transactions::id.eq_any(awaited));
+        }
+        query
+    }
+}
+
+/// Page of transactions matching the filter and total number of them
+pub async fn list_transactions(
+    conn: &TransactionsDb,
+    filter: TxFilter,
+    order: TxOrder,
+    offset: i64,
+    limit: i64,
+) -> QueryResult<(Vec<TransactionListItem>, i64)> {
+    conn.run(move |c| {
+        let total = filter.query().count().get_result::<i64>(c)?;
+        let query = filter.query().select((
+            transactions::id,
+            transactions::title,
+            transactions::source_account,
+            transactions::status,
+            transactions::collected_weight,
+            transactions::required_weight,
+            transactions::created,
+            transactions::max_time,
+        ));
+        let query = match order {
+            TxOrder::Newest => query.order(transactions::created.desc()),
+            TxOrder::Oldest => query.order(transactions::created.asc()),
+            TxOrder::Expiring => query.order((
+                transactions::max_time.is_null().asc(),
+                transactions::max_time.asc(),
+            )),
+            TxOrder::Title => query.order(transactions::title.asc()),
+        };
+        let items = query
+            .offset(offset)
+            .limit(limit)
+            .load::<TransactionListItem>(c)?;
+        Ok((items, total))
+    })
+    .await
+}
+
+/// Remember how many signatures the transaction collected, whom it waits
+/// for and the signers it was checked against, so pages don't have to ask
+/// Horizon
+pub async fn store_signing_progress(
+    conn: &TransactionsDb,
+    txid: String,
+    max_time: Option<i64>,
+    progress: SigningProgress,
+    accounts: &[SigningAccount],
+) -> QueryResult<()> {
+    let source_account =
+        String::from_utf8_lossy(&accounts[0].account_id.to_encoding()).into_owned();
+    let signers = encode_signers(accounts);
+    conn.run(move |c| {
+        c.transaction(|| {
+            diesel::update(all_transactions.find(txid.clone()))
+                .set((
+                    transactions::source_account.eq(source_account),
+                    transactions::max_time.eq(max_time),
+                    transactions::collected_weight.eq(progress.collected),
+                    transactions::required_weight.eq(progress.required),
+                    transactions::signers.eq(Some(signers)),
+                ))
+                .execute(c)?;
+            diesel::delete(awaited_signers::table.filter(awaited_signers::txid.eq(txid.clone())))
+                .execute(c)?;
+            let rows: Vec<_> = progress
+                .awaiting
+                .iter()
+                .map(|pk| {
+                    (
+                        awaited_signers::txid.eq(txid.clone()),
+                        awaited_signers::signer
+                            .eq(String::from_utf8_lossy(&pk.to_encoding()).into_owned()),
+                    )
+                })
+                .collect();
+            diesel::insert_into(awaited_signers::table)
+                .values(&rows)
+                .execute(c)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Remember the signers of transactions that were never checked
+pub async fn store_signers(
+    conn: &TransactionsDb,
+    txid: String,
+    accounts: &[SigningAccount],
+) -> QueryResult<()> {
+    let signers = encode_signers(accounts);
+    conn.run(move |c| {
+        diesel::update(all_transactions.find(txid))
+            .set(transactions::signers.eq(Some(signers)))
+            .execute(c)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Settled transactions don't wait for anybody
+pub async fn clear_awaited_signers(conn: &TransactionsDb, txid: String) -> QueryResult<()> {
+    conn.run(move |c| {
+        diesel::delete(awaited_signers::table.filter(awaited_signers::txid.eq(txid))).execute(c)
+    })
+    .await?;
+    Ok(())
+}
+
+pub async fn transaction_exists(conn: &TransactionsDb, txid: String) -> QueryResult<bool> {
+    conn.run(move |c| {
+        let count: i64 = all_transactions
+            .filter(transactions::id.eq(txid))
+            .count()
+            .get_result(c)?;
+        Ok(count > 0)
+    })
+    .await
+}
+
+/// Ids of fee bumps that wrap the given transaction
+pub async fn get_fee_bumps(conn: &TransactionsDb, inner_txid: String) -> QueryResult<Vec<String>> {
+    conn.run(move |c| {
+        all_transactions
+            .select(transactions::id)
+            .filter(transactions::inner_txid.eq(inner_txid))
+            .order(transactions::created.desc())
+            .load::<String>(c)
+    })
+    .await
+}
+
+pub async fn store_submission(
+    conn: &TransactionsDb,
+    txid: String,
+    result: SubmitResult,
+) -> QueryResult<()> {
+    conn.run(move |c| {
+        let s = SubmissionCreate {
+            txid,
+            successful: result.successful,
+            ledger: result.ledger.map(|l| l as i32),
+            fee_charged: result.fee_charged,
+            result_codes: result.describe().join("\n"),
+            submitted: chrono::Utc::now().naive_utc(),
+        };
+        diesel::insert_into(submissions::table)
+            .values(&s)
+            .execute(c)
+    })
+    .await?;
+    Ok(())
+}
 
-            let meta = MtlTxMeta {
-                title: tx.title,
-                description: tx.description,
-                history,
+/// Submissions of the transaction, the latest first
+pub async fn get_submissions(conn: &TransactionsDb, txid: String) -> QueryResult<Vec<Submission>> {
+    conn.run(move |c| {
+        submissions::table
+            .filter(submissions::txid.eq(txid))
+            .order(submissions::submitted.desc())
+            .load::<Submission>(c)
+    })
+    .await
+}
+
+/// Ids of transactions opted into auto-submission that are still collecting
+/// signatures or wait for submission
+pub async fn get_pending_auto_submits(conn: &TransactionsDb) -> QueryResult<Vec<String>> {
+    conn.run(move |c| {
+        let pending = [TxStatus::Collecting.name(), TxStatus::Ready.name()];
+        all_transactions
+            .select(transactions::id)
+            .filter(transactions::auto_submit.eq(true))
+            .filter(transactions::status.eq_any(&pending[..]))
+            .load::<String>(c)
+    })
+    .await
+}
+
+/// Move the transaction into the status and record when it happened. Returns
+/// false if the transaction already has the status.
+pub async fn set_status(
+    conn: &TransactionsDb,
+    txid: String,
+    status: TxStatus,
+    reason: Option<String>,
+) -> Result<bool, TxLoadError> {
+    conn.run(move |c| {
+        c.transaction(|| {
+            let current: String = all_transactions
+                .find(txid.clone())
+                .select(transactions::status)
+                .get_result(c)?;
+            let current = TxStatus::from_name(&current)?;
+            if current.transition(status)? == current {
+                return Ok(false);
+            }
+            diesel::update(all_transactions.find(txid.clone()))
+                .set(transactions::status.eq(status.name()))
+                .execute(c)?;
+            let change = StatusChangeCreate {
+                txid,
+                status: status.name().to_owned(),
+                reason,
+                changed: chrono::Utc::now().naive_utc(),
             };
-            result.push(meta);
+            diesel::insert_into(status_changes::table)
+                .values(&change)
+                .execute(c)?;
+            Ok(true)
+        })
+    })
+    .await
+}
+
+/// Status changes of the transaction, the latest first
+pub async fn get_status_changes(
+    conn: &TransactionsDb,
+    txid: String,
+) -> QueryResult<Vec<StatusChange>> {
+    conn.run(move |c| {
+        status_changes::table
+            .filter(status_changes::txid.eq(txid))
+            .order((status_changes::changed.desc(), status_changes::id.desc()))
+            .load::<StatusChange>(c)
+    })
+    .await
+}
+
+/// Ids of transactions that can still change their status, the ones checked
+/// long ago first
+pub async fn get_unsettled_transactions(conn: &TransactionsDb) -> QueryResult<Vec<String>> {
+    conn.run(move |c| {
+        let unsettled = [
+            TxStatus::Collecting.name(),
+            TxStatus::Ready.name(),
+            TxStatus::Submitted.name(),
+            TxStatus::Failed.name(),
+        ];
+        all_transactions
+            .select(transactions::id)
+            .filter(transactions::status.eq_any(&unsettled[..]))
+            .order(transactions::checked.asc())
+            .load::<String>(c)
+    })
+    .await
+}
+
+pub async fn mark_checked(conn: &TransactionsDb, txid: String) -> QueryResult<()> {
+    conn.run(move |c| {
+        diesel::update(all_transactions.find(txid))
+            .set(transactions::checked.eq(chrono::Utc::now().naive_utc()))
+            .execute(c)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Login of a signer that passed web authentication
+#[derive(Queryable, Insertable, Debug, Clone)]
+#[table_name = "sessions"]
+pub struct Session {
+    pub token: String,
+    pub signer: String,
+    pub created: NaiveDateTime,
+    pub expires: NaiveDateTime,
+}
+
+pub async fn create_session(conn: &TransactionsDb, session: Session) -> QueryResult<()> {
+    conn.run(move |c| {
+        diesel::insert_into(sessions::table)
+            .values(&session)
+            .execute(c)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Session with the token unless it has expired
+pub async fn get_session(conn: &TransactionsDb, token: String) -> QueryResult<Option<Session>> {
+    conn.run(move |c| {
+        sessions::table
+            .find(token)
+            .filter(sessions::expires.gt(chrono::Utc::now().naive_utc()))
+            .get_result::<Session>(c)
+            .optional()
+    })
+    .await
+}
+
+/// Remove the session together with the expired ones
+pub async fn delete_session(conn: &TransactionsDb, token: String) -> QueryResult<()> {
+    conn.run(move |c| {
+        diesel::delete(
+            sessions::table.filter(
+                sessions::token
+                    .eq(token)
+                    .or(sessions::expires.le(chrono::Utc::now().naive_utc())),
+            ),
+        )
+        .execute(c)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Somebody is signing the transaction and others better wait
+#[derive(Queryable, Debug, Clone)]
+pub struct TxBlock {
+    pub id: i32,
+    pub txid: String,
+    /// Random id of the blocking browser
+    pub holder: String,
+    /// Signer key of the logged in blocker
+    pub signer: Option<String>,
+    pub reason: String,
+    pub created: NaiveDateTime,
+    pub expires: NaiveDateTime,
+}
+
+impl TxBlock {
+    /// Blocks of logged in signers are held by the signer, other blocks by
+    /// the browser that made them
+    pub fn is_held_by(&self, holder: Option<&str>, signer: Option<&str>) -> bool {
+        match &self.signer {
+            Some(owner) => signer == Some(owner.as_str()),
+            None => holder == Some(self.holder.as_str()),
         }
-        Ok(result)
+    }
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "blocks"]
+pub struct TxBlockCreate {
+    pub txid: String,
+    pub holder: String,
+    pub signer: Option<String>,
+    pub reason: String,
+    pub created: NaiveDateTime,
+    pub expires: NaiveDateTime,
+}
+
+fn load_active_block(c: &diesel::SqliteConnection, txid: &str) -> QueryResult<Option<TxBlock>> {
+    blocks::table
+        .filter(blocks::txid.eq(txid))
+        .filter(blocks::expires.gt(chrono::Utc::now().naive_utc()))
+        .order(blocks::expires.desc())
+        .first::<TxBlock>(c)
+        .optional()
+}
+
+pub async fn get_active_block(conn: &TransactionsDb, txid: String) -> QueryResult<Option<TxBlock>> {
+    conn.run(move |c| load_active_block(c, &txid)).await
+}
+
+/// Store the block unless the transaction is already blocked, returns
+/// whether it was stored
+pub async fn create_block(conn: &TransactionsDb, block: TxBlockCreate) -> QueryResult<bool> {
+    conn.run(move |c| {
+        c.transaction(|| {
+            if load_active_block(c, &block.txid)?.is_some() {
+                return Ok(false);
+            }
+            diesel::delete(blocks::table.filter(blocks::txid.eq(&block.txid))).execute(c)?;
+            diesel::insert_into(blocks::table)
+                .values(&block)
+                .execute(c)?;
+            Ok(true)
+        })
     })
     .await
 }
+
+pub async fn extend_block(
+    conn: &TransactionsDb,
+    id: i32,
+    expires: NaiveDateTime,
+) -> QueryResult<()> {
+    conn.run(move |c| {
+        diesel::update(blocks::table.find(id))
+            .set(blocks::expires.eq(expires))
+            .execute(c)
+    })
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_block(conn: &TransactionsDb, id: i32) -> QueryResult<()> {
+    conn.run(move |c| diesel::delete(blocks::table.find(id)).execute(c))
+        .await?;
+    Ok(())
+}
@@ -7,12 +7,17 @@ extern crate diesel;
 #[macro_use]
 extern crate diesel_migrations;
 
+pub mod api;
+pub mod auth;
 pub mod database;
 pub mod schema;
 
+use auth::{Auth, AuthConfig, AuthError, Caller, Signer};
 use database::*;
+use diesel::QueryResult;
 
-use chrono::{Duration, NaiveDateTime, Utc};
+use chrono::{Duration, TimeZone, Utc};
+use rand::RngCore;
 use rocket::fairing::AdHoc;
 use rocket::form::Form;
 use rocket::fs::{relative, FileServer};
@@ -28,50 +33,153 @@ use tokio::sync::Mutex;
 
 use montelibero_transactions::account::*;
 use montelibero_transactions::error::MtlError;
+use montelibero_transactions::horizon::{HorizonClient, HorizonServer};
+use montelibero_transactions::network::NetworkConfig;
+use montelibero_transactions::registry::{AccountsRegistry, RegistryError};
+use montelibero_transactions::signers::{SignerSet, Thresholds};
+use montelibero_transactions::status::{TxStatus, ALL_STATUSES};
 use montelibero_transactions::transaction::*;
+use substrate_stellar_sdk::PublicKey;
+
+/// Private cookie with the random id the browser blocks transactions under
+const BLOCKER_COOKIE: &str = "blocker";
+
+fn blocker_id(cookies: &CookieJar<'_>) -> Option<String> {
+    cookies
+        .get_private(BLOCKER_COOKIE)
+        .map(|c| c.value().to_owned())
+}
+
+/// Id of the browser, a new one is issued on the first block
+fn issue_blocker_id(cookies: &CookieJar<'_>) -> String {
+    blocker_id(cookies).unwrap_or_else(|| {
+        let mut id = [0; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+        let id = hex::encode(id);
+        cookies.add_private(Cookie::new(BLOCKER_COOKIE, id.clone()));
+        id
+    })
+}
+
+/// Remove the block of the transaction if the caller holds it, returns
+/// whether they did
+async fn release_block(
+    conn: &TransactionsDb,
+    txid: String,
+    holder: Option<&str>,
+    signer: Option<&str>,
+) -> QueryResult<bool> {
+    match get_active_block(conn, txid).await? {
+        Some(block) if block.is_held_by(holder, signer) => {
+            delete_block(conn, block.id).await?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Block shown to the signers
+#[derive(Serialize)]
+pub struct ViewBlock {
+    /// Telegram nick or signer key of the blocker, if they were logged in
+    pub owner: Option<String>,
+    pub reason: String,
+    /// Minutes and seconds left, e.g. 3:12
+    pub left: String,
+}
+
+impl ViewBlock {
+    fn new(users: &UsersMapping, block: &TxBlock) -> Self {
+        let owner = block.signer.as_ref().map(|key| {
+            PublicKey::from_encoding(key)
+                .ok()
+                .and_then(|pk| users.get(&pk))
+                .map_or_else(|| key.clone(), |nick| format!("@{}", nick))
+        });
+        let left = (block.expires - Utc::now().naive_utc())
+            .num_seconds()
+            .max(0);
+        ViewBlock {
+            owner,
+            reason: block.reason.clone(),
+            left: format!("{}:{:02}", left / 60, left % 60),
+        }
+    }
+}
 
 #[derive(Clone)]
 struct Cache {
-    blocks: Arc<Mutex<HashMap<Vec<u8>, NaiveDateTime>>>,
+    /// How long a block lasts unless extended
+    block_duration: Duration,
+    /// Auto-submissions waiting for min time of the transactions
+    scheduled: Arc<Mutex<HashMap<Vec<u8>, u64>>>,
     users: UsersMapping,
     signs: Arc<Mutex<SignsMapping>>,
+    registry_file: String,
+    registry: Arc<Mutex<AccountsRegistry>>,
 }
 
 impl Cache {
-    fn new(users: UsersMapping) -> Self {
+    fn new(
+        users: UsersMapping,
+        registry_file: String,
+        registry: AccountsRegistry,
+        block_duration: Duration,
+    ) -> Self {
         Cache {
-            blocks: Arc::new(Mutex::new(HashMap::new())),
+            block_duration,
+            scheduled: Arc::new(Mutex::new(HashMap::new())),
             users,
             signs: Arc::new(Mutex::new(HashMap::new())),
+            registry_file,
+            registry: Arc::new(Mutex::new(registry)),
         }
     }
 
-    async fn is_blocked(&self, tid: &[u8]) -> bool {
-        if let Some(t) = self.blocks.lock().await.get(tid) {
-            *t > Utc::now().naive_utc()
-        } else {
-            false
-        }
+    async fn schedule_submit(&self, tid: &[u8], at: u64) {
+        self.scheduled.lock().await.insert(tid.to_owned(), at);
     }
 
-    async fn block(&self, tid: &[u8], delay: Duration) {
-        self.blocks
-            .lock()
-            .await
-            .insert(tid.to_owned(), Utc::now().naive_utc() + delay);
+    /// Remove and return transactions which submission time has come
+    async fn take_due_submits(&self) -> Vec<Vec<u8>> {
+        let now = Utc::now().timestamp() as u64;
+        let mut scheduled = self.scheduled.lock().await;
+        let due: Vec<Vec<u8>> = scheduled
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(tid, _)| tid.clone())
+            .collect();
+        for tid in due.iter() {
+            scheduled.remove(tid);
+        }
+        due
     }
 
-    async fn unblock(&self, tid: &[u8]) {
-        self.blocks.lock().await.remove(tid);
+    async fn update_signs(
+        &self,
+        conn: &TransactionsDb,
+        horizon: &dyn HorizonClient,
+    ) -> Result<(), SignsMappingError> {
+        let mut signs_mut = self.signs.lock().await;
+        *signs_mut = read_user_recent_signs(conn, horizon).await?;
+        Ok(())
     }
 
-    async fn update_signs(&self, conn: &TransactionsDb) -> Result<(), SignsMappingError> {
-        let mut signs_mut = self.signs.lock().await;
-        *signs_mut = read_user_recent_signs(conn).await?;
+    async fn reload_registry(&self) -> Result<(), RegistryError> {
+        let registry = AccountsRegistry::load(&self.registry_file)?;
+        *self.registry.lock().await = registry;
         Ok(())
     }
 }
 
+pub type Horizon = Arc<dyn HorizonClient>;
+
+/// How often the scheduled auto-submissions are checked
+const AUTO_SUBMIT_PERIOD: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often unsettled transactions are checked against the network
+const REVALIDATE_PERIOD: std::time::Duration = std::time::Duration::from_secs(300);
+
 pub type SignsMapping = HashMap<substrate_stellar_sdk::PublicKey, u32>;
 
 #[derive(Debug, Error)]
@@ -84,6 +192,7 @@ pub enum SignsMappingError {
 
 pub async fn read_user_recent_signs(
     conn: &TransactionsDb,
+    horizon: &dyn HorizonClient,
 ) -> Result<SignsMapping, SignsMappingError> {
     let month_ago = chrono::Utc::now() - chrono::Duration::days(30);
     let txs = get_transactions(conn, month_ago.naive_utc()).await?;
@@ -95,14 +204,16 @@ pub async fn read_user_recent_signs(
             let account = match acc_cache.get(&account_id) {
                 Some(acc) => acc,
                 None => {
-                    let account = tx.fetch_source_account()?;
-                    acc_cache.insert(account_id.clone(), account);
+                    let account = tx.fetch_source_account(horizon)?;
+                    acc_cache.insert(account_id.clone(), SignerSet::from_account(&account)?);
                     acc_cache.get(&account_id).unwrap()
                 }
             };
-            let signs = tx.get_signed_keys(account)?;
+            let signs = tx.get_signed_keys(horizon.network(), account)?;
             for (s, _) in signs {
-                *result.entry(s).or_insert(1) += 1;
+                if let MtlSignerKey::Ed25519(pk) = s {
+                    *result.entry(pk).or_insert(1) += 1;
+                }
             }
         }
     }
@@ -119,18 +230,28 @@ pub fn index() -> Redirect {
 pub enum ViewError {
     #[error("Transaction id is not hex encoded")]
     InvalidTxid(#[from] hex::FromHexError),
-    #[error("List of transactions is not impelemented")]
-    NotImplemented,
+    #[error("Unknown sorting {0}")]
+    UnknownOrder(String),
+    #[error("Invalid account key {0}")]
+    InvalidAccount(String),
     #[error("{0}")]
     Mtl(#[from] MtlError),
     #[error("{0}")]
     DatabaseError(#[from] TxLoadError),
+    #[error("Database error: {0}")]
+    Diesel(#[from] diesel::result::Error),
+}
+
+fn short_key(key: &str) -> String {
+    format!("{}...{}", &key[0 .. 15], &key[key.len()-15 ..])
 }
 
 #[derive(Serialize)]
 pub struct ViewSigner {
     pub key: String,
     pub short_key: String,
+    pub kind: &'static str,
+    pub is_ed25519: bool,
     pub weight: i32,
     pub signed: bool,
     pub telegram: Option<String>,
@@ -143,25 +264,23 @@ impl ViewSigner {
     pub fn collect(
         telegram_map: &UsersMapping,
         signs_map: &SignsMapping,
-        account: &AccountResponse,
-        signs: &[SignatureHint],
+        account: &SignerSet,
+        signed: &[(MtlSignerKey, i32)],
     ) -> Result<Vec<Self>, MtlError> {
         let mut res = Vec::new();
-        for s in get_mtl_signers(account)? {
-            let signer_weight = s.1;
-            let signer_key = s.0;
+        for (signer_key, signer_weight) in account.signers.iter().cloned() {
             if signer_weight > 0 {
-                let singed_monthly = signs_map.get(&signer_key).copied().unwrap_or(0);
-                let key = std::str::from_utf8(&signer_key.to_encoding())
-                    .unwrap()
-                    .to_owned();
-                let short_key = format!("{}...{}", &key[0 .. 15], &key[key.len()-15 ..]);
+                let pk = signer_key.public_key();
+                let singed_monthly = pk.and_then(|pk| signs_map.get(pk)).copied().unwrap_or(0);
+                let key = signer_key.to_encoding();
                 res.push(ViewSigner {
+                    short_key: short_key(&key),
                     key,
-                    short_key,
+                    kind: signer_key.kind(),
+                    is_ed25519: pk.is_some(),
                     weight: signer_weight,
-                    signed: signs.contains(&signer_key.get_signature_hint()),
-                    telegram: telegram_map.get(&signer_key).cloned(),
+                    signed: signed.iter().any(|(k, _)| *k == signer_key),
+                    telegram: pk.and_then(|pk| telegram_map.get(pk)).cloned(),
                     singed_monthly,
                     is_few_signs: singed_monthly < 4,
                     is_moderate_signs: singed_monthly < 10,
@@ -173,22 +292,216 @@ impl ViewSigner {
     }
 }
 
+/// Transaction wrapped by the viewed fee bump
+#[derive(Serialize)]
+pub struct ViewInnerTx {
+    pub id: String,
+    pub source_account: String,
+    pub body: String,
+    /// Whether the inner transaction was collected by the service
+    pub stored: bool,
+}
+
+impl ViewInnerTx {
+    pub async fn collect(
+        conn: &TransactionsDb,
+        network: &NetworkConfig,
+        tx: &MtlTransaction,
+    ) -> Result<Option<Self>, ViewError> {
+        match tx.inner() {
+            None => Ok(None),
+            Some(inner) => {
+                let id = hex::encode(inner.txid(network));
+                let stored = transaction_exists(conn, id.clone()).await?;
+                Ok(Some(ViewInnerTx {
+                    id,
+                    source_account: std::str::from_utf8(&inner.source_account()?.to_encoding())
+                        .unwrap()
+                        .to_owned(),
+                    body: inner.into_encoding(),
+                    stored,
+                }))
+            }
+        }
+    }
+}
+
+/// Signatures collected for one of the accounts that authorize the transaction
+#[derive(Serialize)]
+pub struct ViewAccount {
+    pub key: String,
+    pub label: Option<String>,
+    pub threshold: &'static str,
+    pub operation: Option<String>,
+    pub required: u8,
+    pub collected: i32,
+    pub is_complete: bool,
+    pub signers: Vec<ViewSigner>,
+    pub signer_diff: Option<ViewSignerDiff>,
+}
+
+/// Signers and thresholds of the account before and after set_options
+#[derive(Serialize)]
+pub struct ViewSignerDiff {
+    pub changes: Vec<ViewSignerChange>,
+    pub thresholds_before: Thresholds,
+    pub thresholds_after: Thresholds,
+    pub total_before: i32,
+    pub total_after: i32,
+}
+
+#[derive(Serialize)]
+pub struct ViewSignerChange {
+    pub key: String,
+    pub short_key: String,
+    pub telegram: Option<String>,
+    pub before: i32,
+    pub after: i32,
+    pub is_added: bool,
+    pub is_removed: bool,
+    pub is_changed: bool,
+}
+
+impl ViewSignerDiff {
+    pub fn collect(
+        telegram_map: &UsersMapping,
+        tx: &MtlTransaction,
+        account: &SigningAccount,
+    ) -> Result<Option<Self>, MtlError> {
+        let after = match tx.simulate_signers(account)? {
+            None => return Ok(None),
+            Some(after) => after,
+        };
+        let before = &account.signers;
+        let changes = before
+            .diff(&after)
+            .into_iter()
+            .map(|c| {
+                let key = c.key.to_encoding();
+                ViewSignerChange {
+                    short_key: short_key(&key),
+                    key,
+                    telegram: c
+                        .key
+                        .public_key()
+                        .and_then(|pk| telegram_map.get(pk))
+                        .cloned(),
+                    before: c.before,
+                    after: c.after,
+                    is_added: c.before == 0 && c.after > 0,
+                    is_removed: c.before > 0 && c.after == 0,
+                    is_changed: c.before > 0 && c.after > 0 && c.before != c.after,
+                }
+            })
+            .collect();
+        Ok(Some(ViewSignerDiff {
+            changes,
+            thresholds_before: before.thresholds,
+            thresholds_after: after.thresholds,
+            total_before: before.total_weight(),
+            total_after: after.total_weight(),
+        }))
+    }
+}
+
+impl ViewAccount {
+    pub fn collect(
+        telegram_map: &UsersMapping,
+        signs_map: &SignsMapping,
+        registry: &AccountsRegistry,
+        network: &NetworkConfig,
+        tx: &MtlTransaction,
+        accounts: &[SigningAccount],
+    ) -> Result<Vec<Self>, MtlError> {
+        let mut res = Vec::new();
+        for acc in accounts {
+            let required = acc.required_weight();
+            let signed = tx.get_signed_keys(network, &acc.signers)?;
+            let collected: i32 = signed.iter().map(|s| s.1).sum();
+            res.push(ViewAccount {
+                key: std::str::from_utf8(&acc.account_id.to_encoding())
+                    .unwrap()
+                    .to_owned(),
+                label: registry.get(&acc.account_id).map(|a| a.label.clone()),
+                threshold: acc.threshold.level.name(),
+                operation: acc
+                    .threshold
+                    .operation
+                    .map(|(i, name)| format!("#{} {}", i + 1, name)),
+                required,
+                collected,
+                is_complete: collected >= required as i32,
+                signers: ViewSigner::collect(telegram_map, signs_map, &acc.signers, &signed)?,
+                signer_diff: ViewSignerDiff::collect(telegram_map, tx, acc)?,
+            });
+        }
+        Ok(res)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ViewSubmission {
+    pub date: String,
+    pub successful: bool,
+    pub ledger: Option<i32>,
+    pub fee_charged: Option<i64>,
+    pub result_codes: Vec<String>,
+}
+
+impl ViewSubmission {
+    pub fn collect(submissions: Vec<Submission>) -> Vec<Self> {
+        submissions
+            .into_iter()
+            .map(|s| ViewSubmission {
+                date: s.submitted.format("%Y-%m-%d %H:%M:%S").to_string(),
+                successful: s.successful,
+                ledger: s.ledger,
+                fee_charged: s.fee_charged,
+                result_codes: s.result_codes.lines().map(|l| l.to_owned()).collect(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+pub struct ViewStatusChange {
+    pub date: String,
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+impl ViewStatusChange {
+    pub fn collect(changes: Vec<StatusChange>) -> Vec<Self> {
+        changes
+            .into_iter()
+            .map(|c| ViewStatusChange {
+                date: c.changed.format("%Y-%m-%d %H:%M:%S").to_string(),
+                status: c.status,
+                reason: c.reason,
+            })
+            .collect()
+    }
+}
+
 #[derive(Serialize)]
 pub struct TxHistoryItem {
     pub number: u32,
     pub date: String,
     pub tx: String,
+    /// Signer key of the uploader if they were logged in
+    pub author: Option<String>,
 }
 
 impl TxHistoryItem {
     pub fn collect(tx: &MtlTxMeta) -> Vec<Self> {
         let mut res = Vec::new();
         let n = tx.history.len();
-        for (i, (tx, t)) in tx.history.iter().enumerate() {
+        for (i, ((mtx, t), author)) in tx.history.iter().zip(tx.authors.iter()).enumerate() {
             res.push(TxHistoryItem {
                 number: (n - i) as u32,
                 date: t.format("%Y-%m-%d %H:%M:%S").to_string(),
-                tx: tx.into_encoding(),
+                tx: mtx.into_encoding(),
+                author: author.clone(),
             })
         }
         res.sort_by(|a, b| a.number.cmp(&b.number));
@@ -196,12 +509,403 @@ impl TxHistoryItem {
     }
 }
 
+/// How many transactions a page of the list shows
+const TX_PAGE_SIZE: i64 = 20;
+/// The awaiting dashboard isn't paginated, signers rarely owe that many
+const AWAITING_LIMIT: i64 = 100;
+
+#[derive(FromForm)]
+struct TxListQuery {
+    status: Option<String>,
+    source: Option<String>,
+    /// Signer key the transactions wait for
+    awaiting: Option<String>,
+    sort: Option<String>,
+    page: Option<i64>,
+}
+
+impl TxListQuery {
+    fn filter(&self) -> Result<(TxFilter, TxOrder), ViewError> {
+        let non_empty = |v: &Option<String>| v.clone().filter(|v| !v.is_empty());
+        let status = match non_empty(&self.status) {
+            None => None,
+            Some(name) => Some(TxStatus::from_name(&name)?),
+        };
+        let source_account = non_empty(&self.source);
+        let awaiting = non_empty(&self.awaiting);
+        for key in source_account.iter().chain(awaiting.iter()) {
+            PublicKey::from_encoding(key).map_err(|_| ViewError::InvalidAccount(key.clone()))?;
+        }
+        let order = match non_empty(&self.sort) {
+            None => TxOrder::default(),
+            Some(name) => TxOrder::from_name(&name).ok_or(ViewError::UnknownOrder(name))?,
+        };
+        Ok((
+            TxFilter {
+                status,
+                source_account,
+                awaiting,
+            },
+            order,
+        ))
+    }
+
+    /// Page numbers start from 1
+    fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    /// Query string that keeps the filters when switching pages
+    fn filter_query(&self) -> String {
+        let params = [
+            ("status", &self.status),
+            ("source", &self.source),
+            ("awaiting", &self.awaiting),
+            ("sort", &self.sort),
+        ];
+        params
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .as_ref()
+                    .filter(|v| !v.is_empty())
+                    .map(|v| format!("{}={}", name, v))
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+#[derive(Serialize)]
+pub struct ViewTxListItem {
+    pub id: String,
+    pub title: String,
+    pub source_account: Option<String>,
+    pub short_source_account: Option<String>,
+    pub source_label: Option<String>,
+    pub status: String,
+    pub collected: i32,
+    pub required: i32,
+    pub is_complete: bool,
+    pub created: String,
+    pub expires: Option<String>,
+}
+
+impl ViewTxListItem {
+    pub fn collect(registry: &AccountsRegistry, items: Vec<TransactionListItem>) -> Vec<Self> {
+        items
+            .into_iter()
+            .map(|item| ViewTxListItem {
+                short_source_account: item.source_account.as_deref().map(short_key),
+                source_label: item
+                    .source_account
+                    .as_ref()
+                    .and_then(|key| PublicKey::from_encoding(key).ok())
+                    .and_then(|pk| registry.get(&pk))
+                    .map(|a| a.label.clone()),
+                source_account: item.source_account,
+                id: item.id,
+                title: item.title,
+                status: item.status,
+                collected: item.collected_weight,
+                required: item.required_weight,
+                is_complete: item.required_weight > 0
+                    && item.collected_weight >= item.required_weight,
+                created: item.created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                expires: item
+                    .max_time
+                    .and_then(|t| Utc.timestamp_opt(t, 0).single())
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Page of the transactions list
+struct TxList {
+    transactions: Vec<ViewTxListItem>,
+    total: i64,
+    page: i64,
+    pages: i64,
+}
+
+async fn load_transaction_list(
+    conn: &TransactionsDb,
+    cache: &Cache,
+    query: &TxListQuery,
+) -> Result<TxList, ViewError> {
+    let (filter, order) = query.filter()?;
+    let page = query.page();
+    let (items, total) =
+        list_transactions(conn, filter, order, (page - 1) * TX_PAGE_SIZE, TX_PAGE_SIZE).await?;
+    let registry = cache.registry.lock().await;
+    Ok(TxList {
+        transactions: ViewTxListItem::collect(&registry, items),
+        total,
+        page,
+        pages: (total + TX_PAGE_SIZE - 1) / TX_PAGE_SIZE,
+    })
+}
+
+async fn view_transaction_list(
+    conn: &TransactionsDb,
+    cache: &Cache,
+    query: &TxListQuery,
+) -> Result<Template, ViewError> {
+    let list = load_transaction_list(conn, cache, query).await?;
+    let accounts: Vec<_> = cache
+        .registry
+        .lock()
+        .await
+        .accounts()
+        .iter()
+        .map(|a| {
+            let key = std::str::from_utf8(&a.account.to_encoding())
+                .unwrap()
+                .to_owned();
+            context! { key: key, label: a.label.clone() }
+        })
+        .collect();
+    let statuses: Vec<&str> = ALL_STATUSES.iter().map(|s| s.name()).collect();
+    let orders: Vec<&str> = ALL_ORDERS.iter().map(|o| o.name()).collect();
+    Ok(Template::render(
+        "list-tx",
+        &context! {
+            title: "Montelibero multisignature service",
+            parent: "base",
+            menu_view_tx: true,
+            is_error: false,
+            transactions: list.transactions,
+            total: list.total,
+            page: list.page,
+            pages: list.pages,
+            prev_page: if list.page > 1 { Some(list.page - 1) } else { None },
+            next_page: if list.page < list.pages { Some(list.page + 1) } else { None },
+            filter_query: query.filter_query(),
+            statuses,
+            orders,
+            accounts,
+            status: query.status.clone(),
+            source: query.source.clone(),
+            awaiting: query.awaiting.clone(),
+            sort: query.sort.clone(),
+        },
+    ))
+}
+
+/// Human readable duration like `2d 5h` or `12m`
+fn time_left(seconds: i64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+    let minutes = seconds % 3600 / 60;
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Open transaction that still needs signature of the signer
+#[derive(Serialize)]
+pub struct ViewAwaitingTx {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// Source account of the transaction
+    pub account: Option<String>,
+    pub account_label: Option<String>,
+    pub collected: i32,
+    pub required: i32,
+    /// Weight the source account still lacks
+    pub remaining: i32,
+    pub expires: Option<String>,
+    /// Seconds left until max time of the transaction
+    pub seconds_left: Option<i64>,
+    pub time_left: Option<String>,
+}
+
+impl ViewAwaitingTx {
+    /// Uses the signing progress stored on the last status refresh, so
+    /// polling the dashboard doesn't hit Horizon
+    async fn collect(
+        conn: &TransactionsDb,
+        cache: &Cache,
+        signer: &PublicKey,
+    ) -> Result<Vec<Self>, ViewError> {
+        let filter = TxFilter {
+            awaiting: Some(
+                std::str::from_utf8(&signer.to_encoding())
+                    .unwrap()
+                    .to_owned(),
+            ),
+            ..TxFilter::default()
+        };
+        let (items, _) =
+            list_transactions(conn, filter, TxOrder::Expiring, 0, AWAITING_LIMIT).await?;
+        let now = Utc::now().timestamp();
+        let registry = cache.registry.lock().await;
+        Ok(items
+            .into_iter()
+            .map(|item| {
+                let seconds_left = item.max_time.map(|t| (t - now).max(0));
+                ViewAwaitingTx {
+                    account_label: item
+                        .source_account
+                        .as_ref()
+                        .and_then(|key| PublicKey::from_encoding(key).ok())
+                        .and_then(|pk| registry.get(&pk))
+                        .map(|a| a.label.clone()),
+                    account: item.source_account,
+                    id: item.id,
+                    title: item.title,
+                    status: item.status,
+                    collected: item.collected_weight,
+                    required: item.required_weight,
+                    remaining: (item.required_weight - item.collected_weight).max(0),
+                    expires: item
+                        .max_time
+                        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+                        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+                    seconds_left,
+                    time_left: seconds_left.map(time_left),
+                }
+            })
+            .collect())
+    }
+}
+
+#[derive(Serialize)]
+struct AwaitingResp {
+    transactions: Vec<ViewAwaitingTx>,
+    error: Option<String>,
+}
+
+/// Open transactions the signer hasn't signed yet. The page can be
+/// bookmarked with the key in the query.
+#[get("/awaiting?<key>")]
+async fn awaiting_signature(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    key: Option<String>,
+) -> Template {
+    async fn awaiting(
+        conn: &TransactionsDb,
+        cache: &Cache,
+        key: &str,
+    ) -> Result<Vec<ViewAwaitingTx>, ViewError> {
+        let signer = PublicKey::from_encoding(key).map_err(MtlError::from)?;
+        ViewAwaitingTx::collect(conn, cache, &signer).await
+    }
+
+    let key = key.filter(|k| !k.is_empty());
+    let result = match &key {
+        None => Ok(Vec::new()),
+        Some(key) => awaiting(&conn, cache, key).await,
+    };
+    let telegram = key
+        .as_ref()
+        .and_then(|k| PublicKey::from_encoding(k).ok())
+        .and_then(|pk| cache.users.get(&pk).cloned());
+    match result {
+        Ok(transactions) => Template::render(
+            "awaiting-tx",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_awaiting: true,
+                is_error: false,
+                key,
+                telegram,
+                transactions,
+            },
+        ),
+        Err(e) => Template::render(
+            "awaiting-tx",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_awaiting: true,
+                is_error: true,
+                error_msg: format!("{}", e),
+                key,
+            },
+        ),
+    }
+}
+
+/// Same as the awaiting page for signers' bots to poll
+#[get("/awaiting/json?<key>")]
+async fn awaiting_signature_json(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    key: String,
+) -> Json<AwaitingResp> {
+    let result = match PublicKey::from_encoding(&key) {
+        Err(e) => Err(ViewError::from(MtlError::from(e))),
+        Ok(signer) => ViewAwaitingTx::collect(&conn, cache, &signer).await,
+    };
+    match result {
+        Ok(transactions) => Json(AwaitingResp {
+            transactions,
+            error: None,
+        }),
+        Err(e) => Json(AwaitingResp {
+            transactions: Vec::new(),
+            error: Some(format!("{}", e)),
+        }),
+    }
+}
+
+#[get("/view?<query..>", rank = 2)]
+async fn view_transactions(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    query: TxListQuery,
+) -> Template {
+    match view_transaction_list(&conn, cache, &query).await {
+        Ok(t) => t,
+        Err(e) => Template::render(
+            "list-tx",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_view_tx: true,
+                is_error: true,
+                error_msg: format!("{}", e)
+            },
+        ),
+    }
+}
+
+/// Signing accounts as of the last status check. Only transactions that were
+/// never checked ask Horizon, the revalidation worker keeps the rest fresh.
+pub async fn stored_signing_accounts(
+    conn: &TransactionsDb,
+    horizon: &dyn HorizonClient,
+    meta: &MtlTxMeta,
+    txid: String,
+) -> Result<Vec<SigningAccount>, ViewError> {
+    let tx = meta.current().0;
+    if let Some(known) = &meta.signers {
+        if let Ok(accounts) = tx.known_signing_accounts(known) {
+            return Ok(accounts);
+        }
+    }
+    let accounts = tx.fetch_signing_accounts(horizon)?;
+    store_signers(conn, txid, &accounts).await?;
+    Ok(accounts)
+}
+
 #[get("/view?<tid>")]
 async fn view_transaction(
     conn: TransactionsDb,
     cache: &State<Cache>,
+    horizon: &State<Horizon>,
     cookies: &CookieJar<'_>,
-    tid: Option<String>,
+    caller: Caller,
+    tid: String,
 ) -> Template {
     fn render_error(err_message: &str) -> Template {
         Template::render(
@@ -219,85 +923,119 @@ async fn view_transaction(
     async fn view(
         conn: TransactionsDb,
         cache: &State<Cache>,
+        horizon: &dyn HorizonClient,
         cookies: &CookieJar<'_>,
-        mtid: Option<String>,
+        signer: Option<String>,
+        tid: String,
     ) -> Result<Template, ViewError> {
-        let txid = match mtid {
-            None => return Err(ViewError::NotImplemented),
-            Some(v) => hex::decode(&v)?,
-        };
+        let txid = hex::decode(&tid)?;
         let tx = get_transaction(&conn, txid.clone()).await?;
+        let users = &cache.users;
         let curr_tx = tx.current().0;
-
-        async fn render_tx(
-            cache: &State<Cache>,
-            cookies: &CookieJar<'_>,
-            txid: &[u8],
-            tx: &MtlTxMeta,
-            published: bool,
-            invalid: Option<String>,
-        ) -> Result<Template, ViewError> {
-            let users = &cache.users;
-            let curr_tx = tx.current().0;
-            let is_blocked = cache.is_blocked(txid).await;
-            let mut is_blocker = cookies.get("is_blocker").is_some();
-            if !is_blocked && is_blocker {
-                cookies.remove(Cookie::new("is_blocker", ""));
-                is_blocker = false;
+        let block = get_active_block(&conn, hex::encode(&txid)).await?;
+        let is_blocked = block.is_some();
+        let holder = blocker_id(cookies);
+        let is_blocker =
+            matches!(&block, Some(b) if b.is_held_by(holder.as_deref(), signer.as_deref()));
+        let tx_block = block.as_ref().map(|b| ViewBlock::new(users, b));
+        let source_account = curr_tx.source_account()?;
+        let accounts = stored_signing_accounts(&conn, horizon, &tx, hex::encode(&txid)).await?;
+        let (managed, tx_accounts) = {
+            let registry = cache.registry.lock().await;
+            let signs_map = cache.signs.lock().await;
+            (
+                registry.get(&source_account).cloned(),
+                ViewAccount::collect(
+                    users,
+                    &signs_map,
+                    &registry,
+                    horizon.network(),
+                    &curr_tx,
+                    &accounts,
+                )?,
+            )
+        };
+        let mut tx_ignorants: Vec<String> = Vec::new();
+        for s in tx_accounts.iter().flat_map(|a| a.signers.iter()) {
+            if let Some(telegram) = &s.telegram {
+                if !s.signed && !tx_ignorants.contains(telegram) {
+                    tx_ignorants.push(telegram.clone());
+                }
             }
-            let account = curr_tx.fetch_source_account()?;
-            let signs = curr_tx.get_signed_keys(&account)?;
-            let hints: Vec<SignatureHint> =
-                signs.iter().map(|s| s.0.get_signature_hint()).collect();
-            let tx_collected: i32 = signs.iter().map(|s| s.1).sum();
-            let tx_signers = {
-                let signs_map = cache.signs.lock().await;
-                ViewSigner::collect(users, &signs_map, &account, &hints)?
-            };
-            let tx_ignorants: Vec<String> = tx_signers
-                .iter()
-                .filter(|s| !s.signed && s.telegram.is_some())
-                .map(|s| s.telegram.clone().unwrap())
-                .collect();
-            let tx_history = TxHistoryItem::collect(tx);
-            Ok(Template::render(
-                "view-tx",
-                &context! {
-                    title: "Montelibero multisignature service",
-                    parent: "base",
-                    menu_view_tx: true,
-                    is_error: false,
-                    tx_id: hex::encode(txid),
-                    tx_title: tx.title.clone(),
-                    tx_description: tx.description.clone(),
-                    tx_last: curr_tx.into_encoding(),
-                    tx_required: get_required_weight(&account),
-                    tx_collected,
-                    is_blocked,
-                    is_blocker,
-                    tx_signers,
-                    tx_ignorants,
-                    tx_published: published,
-                    tx_updates: tx.history.len(),
-                    tx_invalid: invalid.is_some(),
-                    tx_invalid_msg: invalid,
-                    tx_history,
-                },
-            ))
         }
-
-        match curr_tx.is_published() {
-            Ok(true) => render_tx(cache, cookies, &txid, &tx, true, None).await,
-            _ => match curr_tx.validate_create() {
-                Ok(_) => render_tx(cache, cookies, &txid, &tx, false, None).await,
-                Err(e) => {
-                    render_tx(cache, cookies, &txid, &tx, false, Some(format!("{}", e))).await
-                }
+        let tx_invalid_signatures: Vec<String> = curr_tx
+            .invalid_signatures(horizon.network(), &accounts)?
+            .iter()
+            .map(|s| hex::encode(s.hint))
+            .collect();
+        let tx_history = TxHistoryItem::collect(&tx);
+        let tx_inner = ViewInnerTx::collect(&conn, horizon.network(), &curr_tx).await?;
+        let tx_fee_bumps = get_fee_bumps(&conn, hex::encode(&txid)).await?;
+        let tx_submissions =
+            ViewSubmission::collect(get_submissions(&conn, hex::encode(&txid)).await?);
+        let status_changes = get_status_changes(&conn, hex::encode(&txid)).await?;
+        let tx_status_reason = status_changes.first().and_then(|c| c.reason.clone());
+        let tx_status_changes = ViewStatusChange::collect(status_changes);
+        let can_submit = tx.status == TxStatus::Ready;
+        let caller_key = signer
+            .as_ref()
+            .and_then(|k| PublicKey::from_encoding(k).ok());
+        let can_cancel = tx.status.can_transition_to(TxStatus::Cancelled)
+            && may_cancel(
+                &accounts,
+                tx.authors.last().cloned().flatten().as_deref(),
+                caller_key.as_ref(),
+            );
+        Ok(Template::render(
+            "view-tx",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_view_tx: true,
+                is_error: false,
+                tx_id: hex::encode(txid),
+                tx_title: tx.title.clone(),
+                tx_description: tx.description.clone(),
+                tx_author: tx.authors.last().cloned().flatten(),
+                tx_account: std::str::from_utf8(&source_account.to_encoding()).unwrap(),
+                tx_account_label: managed.as_ref().map(|a| a.label.clone()),
+                tx_account_description: managed.map(|a| a.description),
+                tx_last: curr_tx.into_encoding(),
+                tx_summary: curr_tx.summary(),
+                tx_risks: curr_tx.risks(),
+                tx_accounts,
+                is_blocked,
+                is_blocker,
+                tx_block,
+                block_minutes: cache.block_duration.num_minutes(),
+                tx_ignorants,
+                tx_invalid_signatures,
+                tx_status: tx.status.name(),
+                tx_status_reason,
+                tx_status_changes,
+                tx_checked: tx
+                    .checked
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+                tx_published: tx.status == TxStatus::Published,
+                tx_failed: tx.status == TxStatus::Failed,
+                tx_final: tx.status.is_final(),
+                tx_updates: tx.history.len(),
+                tx_history,
+                is_fee_bump: curr_tx.is_fee_bump(),
+                tx_inner,
+                tx_fee_bumps,
+                tx_submissions,
+                can_submit,
+                can_cancel,
+                tx_auto_submit: tx.auto_submit,
+                tx_valid_since: curr_tx.valid_since(),
+                is_testnet: horizon.network().is_testnet(),
             },
-        }
+        ))
     }
 
-    match view(conn, cache, cookies, tid).await {
+    let signer = caller.key();
+    match view(conn, cache, horizon.inner().as_ref(), cookies, signer, tid).await {
         Ok(t) => t,
         Err(e) => render_error(&format!("{}", e)),
     }
@@ -309,8 +1047,16 @@ pub enum BlockError {
     InvalidTxid(#[from] hex::FromHexError),
     #[error("Transaction is already blocked")]
     AlreadyBlocked,
+    #[error("Transaction is not blocked")]
+    NotBlocked,
+    #[error("Transaction is blocked by somebody else")]
+    NotHolder,
     #[error("{0}")]
     DatabaseError(#[from] TxLoadError),
+    #[error("Database error: {0}")]
+    Diesel(#[from] diesel::result::Error),
+    #[error("{0}")]
+    Auth(#[from] AuthError),
 }
 
 #[derive(Serialize)]
@@ -318,32 +1064,81 @@ struct BlockResp {
     error: Option<String>,
 }
 
-#[post("/block/<txid>")]
+#[post("/block/<txid>?<reason>")]
 async fn block_transaction(
     conn: TransactionsDb,
     cache: &State<Cache>,
+    caller: Caller,
     cookies: &CookieJar<'_>,
     txid: String,
+    reason: Option<String>,
 ) -> Json<BlockResp> {
     async fn block(
         conn: TransactionsDb,
         cache: &State<Cache>,
-        tid: &str,
+        cookies: &CookieJar<'_>,
+        signer: Result<Option<String>, AuthError>,
+        tid: String,
+        reason: Option<String>,
     ) -> Result<(), BlockError> {
-        let txid = hex::decode(tid)?;
+        let signer = signer?;
+        let txid = hex::decode(&tid)?;
         let _ = get_transaction(&conn, txid.clone()).await?;
-        if cache.is_blocked(&txid).await {
+        let created = Utc::now().naive_utc();
+        let block = TxBlockCreate {
+            txid: hex::encode(txid),
+            holder: issue_blocker_id(cookies),
+            signer,
+            reason: reason.unwrap_or_default(),
+            created,
+            expires: created + cache.block_duration,
+        };
+        if !create_block(&conn, block).await? {
             return Err(BlockError::AlreadyBlocked);
         }
-        cache.block(&txid, Duration::minutes(5)).await;
         Ok(())
     }
 
-    match block(conn, cache, &txid).await {
-        Ok(_) => {
-            cookies.add(Cookie::new("is_blocker", ""));
-            Json(BlockResp { error: None })
+    match block(conn, cache, cookies, caller.author(), txid, reason).await {
+        Ok(_) => Json(BlockResp { error: None }),
+        Err(e) => Json(BlockResp {
+            error: Some(format!("{}", e)),
+        }),
+    }
+}
+
+/// Give the holder of the block another full block duration
+#[post("/block/<txid>/extend")]
+async fn extend_transaction_block(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    caller: Caller,
+    cookies: &CookieJar<'_>,
+    txid: String,
+) -> Json<BlockResp> {
+    async fn extend(
+        conn: TransactionsDb,
+        cache: &State<Cache>,
+        cookies: &CookieJar<'_>,
+        caller: Caller,
+        tid: String,
+    ) -> Result<(), BlockError> {
+        caller.authorize()?;
+        let txid = hex::encode(hex::decode(&tid)?);
+        let block = get_active_block(&conn, txid)
+            .await?
+            .ok_or(BlockError::NotBlocked)?;
+        let holder = blocker_id(cookies);
+        if !block.is_held_by(holder.as_deref(), caller.key().as_deref()) {
+            return Err(BlockError::NotHolder);
         }
+        let expires = Utc::now().naive_utc() + cache.block_duration;
+        extend_block(&conn, block.id, expires).await?;
+        Ok(())
+    }
+
+    match extend(conn, cache, cookies, caller, txid).await {
+        Ok(_) => Json(BlockResp { error: None }),
         Err(e) => Json(BlockResp {
             error: Some(format!("{}", e)),
         }),
@@ -356,8 +1151,14 @@ pub enum UnBlockError {
     InvalidTxid(#[from] hex::FromHexError),
     #[error("Transaction is not blocked")]
     NotBlocked,
+    #[error("Transaction is blocked by somebody else")]
+    NotHolder,
     #[error("{0}")]
     DatabaseError(#[from] TxLoadError),
+    #[error("Database error: {0}")]
+    Diesel(#[from] diesel::result::Error),
+    #[error("{0}")]
+    Auth(#[from] AuthError),
 }
 
 #[derive(Serialize)]
@@ -368,29 +1169,32 @@ struct UnBlockResp {
 #[post("/unblock/<txid>")]
 async fn unblock_transaction(
     conn: TransactionsDb,
-    cache: &State<Cache>,
+    caller: Caller,
     cookies: &CookieJar<'_>,
     txid: String,
 ) -> Json<UnBlockResp> {
     async fn unblock(
         conn: TransactionsDb,
-        cache: &State<Cache>,
-        tid: &str,
+        cookies: &CookieJar<'_>,
+        author: Result<Option<String>, AuthError>,
+        tid: String,
     ) -> Result<(), UnBlockError> {
-        let txid = hex::decode(tid)?;
+        let signer = author?;
+        let txid = hex::decode(&tid)?;
         let _ = get_transaction(&conn, txid.clone()).await?;
-        if !cache.is_blocked(&txid).await {
+        let tid = hex::encode(txid);
+        if get_active_block(&conn, tid.clone()).await?.is_none() {
             return Err(UnBlockError::NotBlocked);
         }
-        cache.unblock(&txid).await;
+        let holder = blocker_id(cookies);
+        if !release_block(&conn, tid, holder.as_deref(), signer.as_deref()).await? {
+            return Err(UnBlockError::NotHolder);
+        }
         Ok(())
     }
 
-    match unblock(conn, cache, &txid).await {
-        Ok(_) => {
-            cookies.remove(Cookie::new("is_blocker", ""));
-            Json(UnBlockResp { error: None })
-        }
+    match unblock(conn, cookies, caller.author(), txid).await {
+        Ok(_) => Json(UnBlockResp { error: None }),
         Err(e) => Json(UnBlockResp {
             error: Some(format!("{}", e)),
         }),
@@ -414,10 +1218,17 @@ struct CreateTx {
     tx_title: String,
     tx_description: String,
     tx_body: String,
+    auto_submit: bool,
 }
 
 #[post("/create", data = "<tx>")]
-async fn post_transaction(conn: TransactionsDb, tx: Form<CreateTx>) -> Template {
+async fn post_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    tx: Form<CreateTx>,
+) -> Template {
     fn render_error(err_message: &str) -> Template {
         Template::render(
             "create-tx-response",
@@ -431,39 +1242,91 @@ async fn post_transaction(conn: TransactionsDb, tx: Form<CreateTx>) -> Template
         )
     }
 
-    if tx.tx_body.is_empty() {
-        render_error("Transaction body is empty")
-    } else if tx.tx_title.is_empty() {
-        render_error("Transaction title is empty")
-    } else {
-        match validate_mtl_tx(&tx.tx_body) {
-            Ok(mtx) => {
-                match store_transaction(
-                    &conn,
-                    mtx.clone(),
-                    tx.tx_title.clone(),
-                    tx.tx_description.clone(),
-                )
-                .await
-                {
-                    Ok(_) => Template::render(
-                        "create-tx-response",
-                        &context! {
-                            title: "Montelibero multisignature service",
-                            parent: "base",
-                            menu_create_tx: true,
-                            txid: hex::encode(mtx.txid()),
-                            is_error: false,
-                        },
-                    ),
-                    Err(e) => render_error(&format!("{}", e)),
-                }
-            }
-            Err(e) => render_error(&format!("{}", e)),
-        }
+    let author = match caller.author() {
+        Ok(author) => author,
+        Err(e) => return render_error(&format!("{}", e)),
+    };
+    let tx = tx.into_inner();
+    let new_tx = NewTransaction {
+        title: tx.tx_title,
+        description: tx.tx_description,
+        body: tx.tx_body,
+        auto_submit: tx.auto_submit,
+    };
+    match create(&conn, cache, horizon.inner(), new_tx, author).await {
+        Ok(txid) => Template::render(
+            "create-tx-response",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_create_tx: true,
+                txid: hex::encode(txid),
+                is_error: false,
+            },
+        ),
+        Err(e) => render_error(&format!("{}", e)),
     }
 }
 
+#[derive(Debug, Error)]
+pub enum CreateError {
+    #[error("Transaction body is empty")]
+    TransactionEmpty,
+    #[error("Transaction title is empty")]
+    TitleEmpty,
+    #[error("{0}")]
+    MtlError(#[from] MtlError),
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] diesel::result::Error),
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewTransaction {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Envelope in base64 XDR
+    pub body: String,
+    #[serde(default)]
+    pub auto_submit: bool,
+}
+
+/// Validate and store new transaction, returns its id
+async fn create(
+    conn: &TransactionsDb,
+    cache: &Cache,
+    horizon: &Horizon,
+    tx: NewTransaction,
+    author: Option<String>,
+) -> Result<Vec<u8>, CreateError> {
+    if tx.body.is_empty() {
+        return Err(CreateError::TransactionEmpty);
+    }
+    if tx.title.is_empty() {
+        return Err(CreateError::TitleEmpty);
+    }
+    let mtx = {
+        let registry = cache.registry.lock().await;
+        validate_mtl_tx(horizon, &registry, &tx.body)?
+    };
+    let network = horizon.network();
+    store_transaction(
+        conn,
+        network,
+        mtx.clone(),
+        tx.title,
+        tx.description,
+        tx.auto_submit,
+        author,
+    )
+    .await?;
+    let txid = mtx.txid(network);
+    try_refresh_status(conn, horizon, txid.clone()).await;
+    try_auto_submit(conn, cache, horizon, txid.clone()).await;
+    Ok(txid)
+}
+
 #[derive(FromForm)]
 struct UpdateTx {
     tx_body: String,
@@ -485,12 +1348,17 @@ pub enum UpdateError {
     DatabaseError(#[from] diesel::result::Error),
     #[error("Failed to update signs mapping: {0}")]
     SignsError(#[from] SignsMappingError),
+    #[error("{0}")]
+    Auth(#[from] AuthError),
 }
 
 #[post("/update", data = "<tx>")]
 async fn update_transaction(
     conn: TransactionsDb,
     cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    cookies: &CookieJar<'_>,
     tx: Form<UpdateTx>,
 ) -> Result<Redirect, Template> {
     fn render_error(err_message: &str) -> Template {
@@ -506,36 +1374,447 @@ async fn update_transaction(
         )
     }
 
-    async fn update(
-        conn: TransactionsDb,
-        cache: &State<Cache>,
-        tx: Form<UpdateTx>,
-    ) -> Result<MtlTransaction, UpdateError> {
-        if tx.tx_body.is_empty() {
-            return Err(UpdateError::TransactionEmpty);
+    let author = caller
+        .author()
+        .map_err(|e| render_error(&format!("{}", e)))?;
+    match update(&conn, cache, horizon.inner(), &tx.tx_body, author).await {
+        Err(e) => Err(render_error(&format!("{}", e))),
+        Ok(tx) => {
+            let txid = tx.txid(horizon.network());
+            release_own_block(&conn, cookies, &caller, &txid).await;
+            let url = uri!(view_transaction(tid = hex::encode(txid)));
+            Ok(Redirect::to(url))
+        }
+    }
+}
+
+/// The caller has signed, so their block is not needed anymore
+async fn release_own_block(
+    conn: &TransactionsDb,
+    cookies: &CookieJar<'_>,
+    caller: &Caller,
+    txid: &[u8],
+) {
+    let holder = blocker_id(cookies);
+    let signer = caller.key();
+    let tid = hex::encode(txid);
+    if let Err(e) = release_block(conn, tid.clone(), holder.as_deref(), signer.as_deref()).await {
+        warn_!("Failed to release block of transaction {}: {}", tid, e);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CancelError {
+    #[error("Transaction id is not hex encoded")]
+    InvalidTxid(#[from] hex::FromHexError),
+    #[error("{0}")]
+    DatabaseError(#[from] TxLoadError),
+    #[error("Database error: {0}")]
+    Diesel(#[from] diesel::result::Error),
+    #[error("{0}")]
+    Auth(#[from] AuthError),
+    #[error("{0}")]
+    Accounts(#[from] ViewError),
+}
+
+/// Withdraw the transaction, it isn't awaited from signers anymore. Only the
+/// author or a signer of the accounts can do it, whatever `signers_only` is.
+async fn cancel(
+    conn: &TransactionsDb,
+    horizon: &Horizon,
+    caller: &Caller,
+    tid: &str,
+    reason: Option<String>,
+) -> Result<(), CancelError> {
+    let signer = caller.signer.as_ref().ok_or(AuthError::LoginRequired)?;
+    let txid = hex::encode(hex::decode(tid)?);
+    let meta = get_transaction(conn, hex::decode(&txid)?).await?;
+    let accounts = stored_signing_accounts(conn, horizon, &meta, txid.clone()).await?;
+    let author = meta.authors.last().cloned().flatten();
+    if !may_cancel(&accounts, author.as_deref(), Some(&signer.key)) {
+        return Err(AuthError::NotTxSigner(signer.encoding()).into());
+    }
+    let reason = reason.filter(|r| !r.is_empty());
+    set_status(conn, txid.clone(), TxStatus::Cancelled, reason).await?;
+    clear_awaited_signers(conn, txid).await?;
+    Ok(())
+}
+
+#[derive(FromForm)]
+struct CancelForm {
+    reason: String,
+}
+
+#[post("/cancel/<txid>", data = "<form>")]
+async fn cancel_transaction(
+    conn: TransactionsDb,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+    form: Form<CancelForm>,
+) -> Result<Redirect, Template> {
+    let horizon = horizon.inner();
+    match cancel(
+        &conn,
+        horizon,
+        &caller,
+        &txid,
+        Some(form.into_inner().reason),
+    )
+    .await
+    {
+        Err(e) => Err(Template::render(
+            "create-tx-response",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_view_tx: true,
+                is_error: true,
+                error_msg: format!("{}", e)
+            },
+        )),
+        Ok(_) => Ok(Redirect::to(uri!(view_transaction(tid = txid)))),
+    }
+}
+
+#[derive(FromForm)]
+struct DetachedSignature {
+    signer: String,
+    signature: String,
+}
+
+#[post("/sign/<txid>", data = "<sign>")]
+async fn sign_transaction(
+    conn: TransactionsDb,
+    cache: &State<Cache>,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    cookies: &CookieJar<'_>,
+    txid: String,
+    sign: Form<DetachedSignature>,
+) -> Result<Redirect, Template> {
+    let horizon = horizon.inner();
+    let result = match caller.author() {
+        Ok(author) => {
+            add_signature(
+                &conn,
+                cache,
+                horizon,
+                &txid,
+                &sign.signer,
+                &sign.signature,
+                author,
+            )
+            .await
+        }
+        Err(e) => Err(e.into()),
+    };
+    match result {
+        Err(e) => Err(Template::render(
+            "create-tx-response",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_view_tx: true,
+                is_error: true,
+                error_msg: format!("{}", e)
+            },
+        )),
+        Ok(tx) => {
+            release_own_block(&conn, cookies, &caller, &tx.txid(horizon.network())).await;
+            Ok(Redirect::to(uri!(view_transaction(tid = txid))))
         }
-        let mtx = validate_mtl_tx(&tx.tx_body)?;
-        let txid = mtx.txid();
-        let old_tx = get_transaction(&conn, txid.clone()).await?;
-        old_tx.current().0.validate_update(&mtx)?;
-        if mtx.into_bytes() == old_tx.current().0.into_bytes() {
-            return Err(UpdateError::TransactionNotChanged);
+    }
+}
+
+/// Append signature made over the transaction hash outside of the envelope
+/// and store it as a regular update
+async fn add_signature(
+    conn: &TransactionsDb,
+    cache: &Cache,
+    horizon: &Horizon,
+    tid: &str,
+    signer: &str,
+    signature: &str,
+    author: Option<String>,
+) -> Result<MtlTransaction, UpdateError> {
+    let txid = hex::decode(tid)?;
+    let signer = PublicKey::from_encoding(signer.trim()).map_err(MtlError::from)?;
+    let signature = decode_signature(signature)?;
+    let stored = get_transaction(conn, txid).await?.current().0;
+    let upload = stored.with_detached_signature(horizon.network(), &signer, &signature)?;
+    update(conn, cache, horizon, &upload.into_encoding(), author).await
+}
+
+/// Merge signatures of the uploaded envelope into the stored transaction
+async fn update(
+    conn: &TransactionsDb,
+    cache: &Cache,
+    horizon: &Horizon,
+    body: &str,
+    author: Option<String>,
+) -> Result<MtlTransaction, UpdateError> {
+    if body.is_empty() {
+        return Err(UpdateError::TransactionEmpty);
+    }
+    let upload = {
+        let registry = cache.registry.lock().await;
+        parse_mtl_tx(horizon, &registry, &body)?
+    };
+    let network = horizon.network();
+    let txid = upload.txid(network);
+    let old_tx = get_transaction(conn, txid.clone()).await?;
+    let old = &old_tx.current().0;
+    let accounts = upload.fetch_signing_accounts(horizon)?;
+    upload.guard_invalid_signatures(network, &accounts, old.decorated_signatures())?;
+    // Signers may work in parallel, so the upload doesn't have to include
+    // the stored signatures. Signatures nobody needs are dropped, so the
+    // transaction doesn't fail with txBAD_AUTH_EXTRA.
+    let mtx = old
+        .merge_signatures(network, &accounts, &[upload])?
+        .trim_signatures(network, &accounts, old.decorated_signatures())?;
+    mtx.validate_create(horizon)?;
+    old.validate_merge(horizon, &mtx)?;
+    if mtx.into_bytes() == old.into_bytes() {
+        return Err(UpdateError::TransactionNotChanged);
+    }
+    store_transaction_update(conn, network, mtx.clone(), author).await?;
+    cache.update_signs(conn, horizon).await?;
+    refresh_status(conn, horizon, txid.clone()).await?;
+    try_auto_submit(conn, cache, horizon, txid).await;
+    Ok(mtx)
+}
+
+#[derive(Debug, Error)]
+pub enum SubmitError {
+    #[error("Transaction id is not hex encoded")]
+    InvalidTxid(#[from] hex::FromHexError),
+    #[error("{0}")]
+    Mtl(#[from] MtlError),
+    #[error("{0}")]
+    DatabaseError(#[from] TxLoadError),
+    #[error("Database error: {0}")]
+    Diesel(#[from] diesel::result::Error),
+    #[error("Transaction is {0}, only ready transactions can be submitted")]
+    NotReady(TxStatus),
+    #[error("{0}")]
+    Auth(#[from] AuthError),
+}
+
+/// Horizon client makes blocking HTTP requests, run them on the blocking
+/// pool so a slow Horizon doesn't stall the threads that serve requests
+async fn off_runtime<T, F>(horizon: &Horizon, f: F) -> Result<T, MtlError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Horizon) -> Result<T, MtlError> + Send + 'static,
+{
+    let horizon = horizon.clone();
+    match tokio::task::spawn_blocking(move || f(&horizon)).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Re-check the transaction against the network and persist its status.
+/// Submitted transactions don't go back to collecting signatures.
+async fn refresh_status(
+    conn: &TransactionsDb,
+    horizon: &Horizon,
+    txid: Vec<u8>,
+) -> Result<TxStatus, TxLoadError> {
+    let meta = get_transaction(conn, txid.clone()).await?;
+    if meta.status.is_final() {
+        return Ok(meta.status);
+    }
+    let tx = meta.current().0;
+    let check = {
+        let tx = tx.clone();
+        off_runtime(horizon, move |horizon| {
+            tx.check_status_with_accounts(horizon)
+        })
+        .await?
+    };
+    let (status, reason) = (check.status, check.reason);
+    let tid = hex::encode(&txid);
+    mark_checked(conn, tid.clone()).await?;
+    match check.accounts {
+        Some(accounts) if !status.is_final() => {
+            let progress = tx.signing_progress(horizon.network(), &accounts)?;
+            let max_time = tx.valid_until().map(|t| t as i64);
+            store_signing_progress(conn, tid, max_time, progress, &accounts).await?;
         }
-        store_transaction_update(&conn, mtx.clone()).await?;
-        cache.unblock(&txid).await;
-        cache.update_signs(&conn).await?;
-        Ok(mtx)
+        _ => clear_awaited_signers(conn, tid).await?,
+    }
+    if meta.status == TxStatus::Submitted
+        && matches!(status, TxStatus::Collecting | TxStatus::Ready)
+    {
+        return Ok(meta.status);
+    }
+    set_status(conn, hex::encode(txid), status, reason).await?;
+    Ok(status)
+}
+
+/// Status refresh failures don't affect the request that triggered it
+async fn try_refresh_status(conn: &TransactionsDb, horizon: &Horizon, txid: Vec<u8>) {
+    let tid = hex::encode(&txid);
+    if let Err(e) = refresh_status(conn, horizon, txid).await {
+        warn_!("Failed to refresh status of transaction {}: {}", tid, e);
+    }
+}
+
+/// Send the ready transaction to Horizon and record the outcome. When the
+/// request itself fails Horizon may still have applied the transaction, so
+/// it stays submitted until the revalidation worker finds out.
+async fn submit_ready(
+    conn: &TransactionsDb,
+    horizon: &Horizon,
+    txid: Vec<u8>,
+    tx: &MtlTransaction,
+) -> Result<(), SubmitError> {
+    let tid = hex::encode(txid);
+    {
+        let tx = tx.clone();
+        off_runtime(horizon, move |horizon| tx.guard_submit(horizon)).await?;
+    }
+    set_status(conn, tid.clone(), TxStatus::Submitted, None).await?;
+    let envelope = tx.envelope();
+    let result = off_runtime(horizon, move |horizon| horizon.submit_transaction(envelope)).await?;
+    if result.successful {
+        set_status(conn, tid.clone(), TxStatus::Published, None).await?;
+    } else {
+        let reason = result.describe().join("; ");
+        set_status(conn, tid.clone(), TxStatus::Failed, Some(reason)).await?;
+    }
+    store_submission(conn, tid, result).await?;
+    Ok(())
+}
+
+#[post("/submit/<txid>")]
+async fn submit_transaction(
+    conn: TransactionsDb,
+    horizon: &State<Horizon>,
+    caller: Caller,
+    txid: String,
+) -> Result<Redirect, Template> {
+    fn render_error(err_message: &str) -> Template {
+        Template::render(
+            "create-tx-response",
+            &context! {
+                title: "Montelibero multisignature service",
+                parent: "base",
+                menu_view_tx: true,
+                is_error: true,
+                error_msg: err_message
+            },
+        )
     }
 
-    match update(conn, cache, tx).await {
+    match submit(&conn, horizon.inner(), &caller, &txid).await {
         Err(e) => Err(render_error(&format!("{}", e))),
-        Ok(tx) => {
-            let url = uri!(view_transaction(tid = Some(hex::encode(tx.txid()))));
-            Ok(Redirect::to(url))
+        Ok(_) => Ok(Redirect::to(uri!(view_transaction(tid = txid)))),
+    }
+}
+
+/// Submit the stored transaction if it has collected enough signatures
+async fn submit(
+    conn: &TransactionsDb,
+    horizon: &Horizon,
+    caller: &Caller,
+    tid: &str,
+) -> Result<(), SubmitError> {
+    caller.authorize()?;
+    let txid = hex::decode(tid)?;
+    let status = refresh_status(conn, horizon, txid.clone()).await?;
+    if status != TxStatus::Ready {
+        return Err(SubmitError::NotReady(status));
+    }
+    let tx = get_transaction(conn, txid.clone()).await?;
+    submit_ready(conn, horizon, txid, &tx.current().0).await
+}
+
+/// Submit the transaction if the creator opted in and it has collected
+/// enough signatures. Transactions that are not valid yet are scheduled.
+/// Only the first attempt is automatic, after a rejection the transaction
+/// has to be resubmitted by hand.
+async fn auto_submit(
+    conn: &TransactionsDb,
+    cache: &Cache,
+    horizon: &Horizon,
+    txid: Vec<u8>,
+) -> Result<(), SubmitError> {
+    let meta = get_transaction(conn, txid.clone()).await?;
+    if !meta.auto_submit || meta.status != TxStatus::Ready {
+        return Ok(());
+    }
+    let changes = get_status_changes(conn, hex::encode(&txid)).await?;
+    let submitted = TxStatus::Submitted.name();
+    if changes.iter().any(|c| c.status == submitted) {
+        return Ok(());
+    }
+    let tx = meta.current().0;
+    let valid_since = tx.valid_since();
+    if valid_since > Utc::now().timestamp() as u64 {
+        cache.schedule_submit(&txid, valid_since).await;
+        return Ok(());
+    }
+    submit_ready(conn, horizon, txid, &tx).await
+}
+
+/// Auto-submission failures don't affect the request that triggered it
+async fn try_auto_submit(conn: &TransactionsDb, cache: &Cache, horizon: &Horizon, txid: Vec<u8>) {
+    let tid = hex::encode(&txid);
+    if let Err(e) = auto_submit(conn, cache, horizon, txid).await {
+        error!("Failed to auto-submit transaction {}: {}", tid, e);
+    }
+}
+
+/// Submits scheduled transactions when their time comes. Transactions that
+/// were waiting before the restart are picked up on start.
+async fn auto_submit_worker(conn: TransactionsDb, cache: Cache, horizon: Horizon) {
+    match get_pending_auto_submits(&conn).await {
+        Ok(pending) => {
+            for tid in pending {
+                if let Ok(txid) = hex::decode(&tid) {
+                    try_refresh_status(&conn, &horizon, txid.clone()).await;
+                    try_auto_submit(&conn, &cache, &horizon, txid).await;
+                }
+            }
+        }
+        Err(e) => error!("Failed to load pending auto-submissions: {}", e),
+    }
+    loop {
+        tokio::time::sleep(AUTO_SUBMIT_PERIOD).await;
+        for txid in cache.take_due_submits().await {
+            try_auto_submit(&conn, &cache, &horizon, txid).await;
         }
     }
 }
 
+/// Keeps statuses of transactions up to date when nobody opens them: they
+/// get published or superseded by other transactions, expire, or signers of
+/// the accounts change. Transactions that became ready are auto-submitted.
+async fn revalidate_worker(conn: TransactionsDb, cache: Cache, horizon: Horizon) {
+    loop {
+        match get_unsettled_transactions(&conn).await {
+            Ok(unsettled) => {
+                for tid in unsettled {
+                    let txid = match hex::decode(&tid) {
+                        Ok(txid) => txid,
+                        Err(_) => continue,
+                    };
+                    match refresh_status(&conn, &horizon, txid.clone()).await {
+                        Ok(TxStatus::Ready) => try_auto_submit(&conn, &cache, &horizon, txid).await,
+                        Ok(_) => {}
+                        Err(e) => error!("Failed to revalidate transaction {}: {}", tid, e),
+                    }
+                }
+            }
+            Err(e) => error!("Failed to load unsettled transactions: {}", e),
+        }
+        tokio::time::sleep(REVALIDATE_PERIOD).await;
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum CheckError {
     #[error("Failed to decode transaction ID")]
@@ -544,6 +1823,8 @@ pub enum CheckError {
     TransactionLoad(#[from] database::TxLoadError),
     #[error("{0}")]
     MtlError(#[from] MtlError),
+    #[error("Database error: {0}")]
+    Diesel(#[from] diesel::result::Error),
 }
 
 #[derive(Serialize)]
@@ -553,35 +1834,30 @@ pub struct CheckResult {
     pub error_msg: Option<String>,
 }
 
-#[get("/check/update/<txid>?<updates>&<block>&<published>")]
+#[get("/check/update/<txid>?<updates>&<block>&<status>")]
 async fn check_update_transaction(
     conn: TransactionsDb,
-    cache: &State<Cache>,
     txid: String,
     updates: u32,
     block: bool,
-    published: bool,
+    status: String,
 ) -> Json<CheckResult> {
     async fn check(
         conn: TransactionsDb,
-        cache: &State<Cache>,
         txid: String,
         updates: u32,
         block: bool,
-        published: bool,
+        status: String,
     ) -> Result<bool, CheckError> {
         let txid = hex::decode(&txid)?;
         let meta = get_transaction(&conn, txid.clone()).await?;
-        let is_blocked = cache.is_blocked(&txid).await;
-        let is_published = meta.current().0.is_published().unwrap_or(false);
-        Ok(
-            block != is_blocked
-                || updates != meta.history.len() as u32
-                || published != is_published,
-        )
+        let is_blocked = get_active_block(&conn, hex::encode(txid)).await?.is_some();
+        Ok(block != is_blocked
+            || updates != meta.history.len() as u32
+            || status != meta.status.name())
     }
 
-    match check(conn, cache, txid, updates, block, published).await {
+    match check(conn, txid, updates, block, status).await {
         Err(e) => Json(CheckResult {
             updated: false,
             is_error: true,
@@ -595,6 +1871,28 @@ async fn check_update_transaction(
     }
 }
 
+#[derive(Serialize)]
+struct ReloadResp {
+    error: Option<String>,
+}
+
+/// Only logged in signers reload the registry, without web authentication it
+/// is reloaded by restarting the service
+#[post("/registry/reload")]
+async fn reload_registry(cache: &State<Cache>, signer: Option<Signer>) -> Json<ReloadResp> {
+    if signer.is_none() {
+        return Json(ReloadResp {
+            error: Some(format!("{}", AuthError::LoginRequired)),
+        });
+    }
+    match cache.reload_registry().await {
+        Ok(_) => Json(ReloadResp { error: None }),
+        Err(e) => Json(ReloadResp {
+            error: Some(format!("{}", e)),
+        }),
+    }
+}
+
 async fn run_migrations(rocket: Rocket<Build>) -> Rocket<Build> {
     embed_migrations!();
 
@@ -608,12 +1906,16 @@ async fn run_migrations(rocket: Rocket<Build>) -> Rocket<Build> {
     rocket
 }
 
-async fn load_signs(rocket: Rocket<Build>, signs_mux: Arc<Mutex<SignsMapping>>) -> Rocket<Build> {
+async fn load_signs(
+    rocket: Rocket<Build>,
+    horizon: Horizon,
+    signs_mux: Arc<Mutex<SignsMapping>>,
+) -> Rocket<Build> {
     let conn = TransactionsDb::get_one(&rocket)
         .await
         .expect("database connection");
 
-    let signs = read_user_recent_signs(&conn)
+    let signs = read_user_recent_signs(&conn, horizon.as_ref())
         .await
         .expect("loaded signatures");
 
@@ -628,6 +1930,29 @@ async fn load_signs(rocket: Rocket<Build>, signs_mux: Arc<Mutex<SignsMapping>>)
 struct Config {
     statics: Option<String>,
     users: String,
+    accounts: String,
+    network: Option<NetworkConfig>,
+    auth: Option<AuthConfig>,
+    /// How long a transaction stays blocked, 5 minutes by default
+    block_minutes: Option<i64>,
+}
+
+/// Longest block the config accepts, a week
+const MAX_BLOCK_MINUTES: i64 = 7 * 24 * 60;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("block_minutes must be from 1 to {}, got {0}", MAX_BLOCK_MINUTES)]
+    BlockMinutes(i64),
+}
+
+impl Config {
+    fn block_duration(&self) -> Result<Duration, ConfigError> {
+        match self.block_minutes.unwrap_or(5) {
+            minutes @ 1..=MAX_BLOCK_MINUTES => Ok(Duration::minutes(minutes)),
+            minutes => Err(ConfigError::BlockMinutes(minutes)),
+        }
+    }
 }
 
 #[launch]
@@ -635,12 +1960,16 @@ fn rocket() -> _ {
     let builder = rocket::build();
     let figment = builder.figment();
     let config: Config = figment.extract().expect("config");
+    let block_duration = config.block_duration().unwrap_or_else(|e| panic!("{}", e));
 
     let statics = config
         .statics
         .unwrap_or_else(|| relative!("static").to_owned());
     let users = get_telegram_mapping(&config.users).unwrap();
-    let cache = Cache::new(users);
+    let horizon: Horizon = Arc::new(HorizonServer::new(config.network.unwrap_or_default()));
+    let registry = AccountsRegistry::load(&config.accounts).expect("accounts registry");
+    let cache = Cache::new(users, config.accounts, registry, block_duration);
+    let auth = Auth::new(config.auth).expect("web authentication config");
     builder
         .mount("/", FileServer::from(&statics))
         .mount(
@@ -650,17 +1979,52 @@ fn rocket() -> _ {
                 create_transaction,
                 post_transaction,
                 view_transaction,
+                view_transactions,
+                awaiting_signature,
+                awaiting_signature_json,
                 block_transaction,
+                extend_transaction_block,
                 unblock_transaction,
                 update_transaction,
+                sign_transaction,
+                submit_transaction,
+                cancel_transaction,
                 check_update_transaction,
+                reload_registry,
             ],
         )
+        .mount("/", auth::routes())
+        .mount("/api/v1", api::routes())
+        .register("/api/v1", api::catchers())
         .manage(cache.clone())
+        .manage(horizon.clone())
+        .manage(auth)
         .attach(Template::fairing())
         .attach(TransactionsDb::fairing())
         .attach(AdHoc::on_ignite("Run Migrations", run_migrations))
-        .attach(AdHoc::on_ignite("Load initial signs", move |rocket| {
-            load_signs(rocket, cache.signs)
+        .attach(AdHoc::on_ignite("Load initial signs", {
+            let horizon = horizon.clone();
+            let signs = cache.signs.clone();
+            move |rocket| load_signs(rocket, horizon, signs)
+        }))
+        .attach(AdHoc::on_liftoff("Revalidation worker", {
+            let cache = cache.clone();
+            let horizon = horizon.clone();
+            move |rocket| {
+                Box::pin(async move {
+                    let conn = TransactionsDb::get_one(rocket)
+                        .await
+                        .expect("database connection");
+                    tokio::spawn(revalidate_worker(conn, cache, horizon));
+                })
+            }
+        }))
+        .attach(AdHoc::on_liftoff("Auto-submission worker", move |rocket| {
+            Box::pin(async move {
+                let conn = TransactionsDb::get_one(rocket)
+                    .await
+                    .expect("database connection");
+                tokio::spawn(auto_submit_worker(conn, cache, horizon));
+            })
         }))
 }
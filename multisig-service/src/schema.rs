@@ -1,9 +1,61 @@
+table! {
+    awaited_signers (id) {
+        id -> Integer,
+        txid -> Text,
+        signer -> Text,
+    }
+}
+
+table! {
+    blocks (id) {
+        id -> Integer,
+        txid -> Text,
+        holder -> Text,
+        signer -> Nullable<Text>,
+        reason -> Text,
+        created -> Timestamp,
+        expires -> Timestamp,
+    }
+}
+
+table! {
+    sessions (token) {
+        token -> Text,
+        signer -> Text,
+        created -> Timestamp,
+        expires -> Timestamp,
+    }
+}
+
+table! {
+    status_changes (id) {
+        id -> Integer,
+        txid -> Text,
+        status -> Text,
+        reason -> Nullable<Text>,
+        changed -> Timestamp,
+    }
+}
+
+table! {
+    submissions (id) {
+        id -> Integer,
+        txid -> Text,
+        successful -> Bool,
+        ledger -> Nullable<Integer>,
+        fee_charged -> Nullable<BigInt>,
+        result_codes -> Text,
+        submitted -> Timestamp,
+    }
+}
+
 table! {
     transaction_updates (id) {
         id -> Integer,
         txid -> Text,
         body -> Binary,
         updated -> Timestamp,
+        author -> Nullable<Text>,
     }
 }
 
@@ -14,9 +66,31 @@ table! {
         description -> Text,
         body -> Binary,
         created -> Timestamp,
+        inner_txid -> Nullable<Text>,
+        auto_submit -> Bool,
+        status -> Text,
+        checked -> Nullable<Timestamp>,
+        source_account -> Nullable<Text>,
+        max_time -> Nullable<BigInt>,
+        collected_weight -> Integer,
+        required_weight -> Integer,
+        author -> Nullable<Text>,
+        signers -> Nullable<Text>,
     }
 }
 
+joinable!(awaited_signers -> transactions (txid));
+joinable!(blocks -> transactions (txid));
+joinable!(status_changes -> transactions (txid));
+joinable!(submissions -> transactions (txid));
 joinable!(transaction_updates -> transactions (txid));
 
-allow_tables_to_appear_in_same_query!(transaction_updates, transactions,);
+allow_tables_to_appear_in_same_query!(
+    awaited_signers,
+    blocks,
+    sessions,
+    status_changes,
+    submissions,
+    transaction_updates,
+    transactions,
+);
@@ -49,6 +49,85 @@ in {
           Contents of file with user mapping
         '';
       };
+      accounts = mkOption {
+        type = types.str;
+        default = builtins.readFile ../../multisig-service/accounts.json;
+        description = ''
+          Contents of file with registry of accounts managed by the service
+        '';
+      };
+      horizon = mkOption {
+        type = types.str;
+        default = "https://horizon.stellar.org";
+        description = ''
+          URL of Horizon server that is used to validate and track transactions.
+        '';
+      };
+      passphrase = mkOption {
+        type = types.str;
+        default = "Public Global Stellar Network ; September 2015";
+        description = ''
+          Passphrase of Stellar network the transactions are signed for.
+        '';
+      };
+      blockMinutes = mkOption {
+        type = types.ints.between 1 10080;
+        default = 5;
+        description = ''
+          How long "Block" marks a transaction as being signed, up to a week.
+        '';
+      };
+      secretKeyFile = mkOption {
+        type = types.str;
+        default = "${cfg.statePath}/secret_key";
+        description = ''
+          File with the base64 encoded 256-bit key that encrypts private
+          cookies (sessions and blocks). Generated on activation if missing.
+        '';
+      };
+      auth = {
+        enable = mkOption {
+          type = types.bool;
+          default = false;
+          description = ''
+            Whether to enable signer login with SEP-10 web authentication.
+          '';
+        };
+        secretFile = mkOption {
+          type = types.str;
+          default = "${cfg.statePath}/auth_secret";
+          description = ''
+            File with the S... seed of the key that signs login challenges.
+          '';
+        };
+        homeDomain = mkOption {
+          type = types.str;
+          description = ''
+            Domain the service is served from, part of the login challenge.
+          '';
+        };
+        webAuthDomain = mkOption {
+          type = types.nullOr types.str;
+          default = null;
+          description = ''
+            Domain of the web authentication endpoint, defaults to the home domain.
+          '';
+        };
+        sessionHours = mkOption {
+          type = types.int;
+          default = 24;
+          description = ''
+            How long a signer stays logged in.
+          '';
+        };
+        signersOnly = mkOption {
+          type = types.bool;
+          default = false;
+          description = ''
+            Only logged in signers can create, update, block and submit transactions.
+          '';
+        };
+      };
       templates = mkOption {
         type = types.str;
         default = "${cfg.package}/share/templates";
@@ -63,13 +142,29 @@ in {
           port=${toString cfg.port}
           statics="${cfg.statics}"
           users="/etc/mtl-users.json"
+          accounts="/etc/mtl-accounts.json"
           template_dir="${cfg.templates}"
+          block_minutes=${toString cfg.blockMinutes}
+
+          [default.network]
+          horizon="${cfg.horizon}"
+          passphrase="${cfg.passphrase}"
 
           [global.databases]
           transactions = { url = "${cfg.statePath}/database.sqlite" }
+        '' + optionalString cfg.auth.enable ''
+
+          [default.auth]
+          secret="@AUTH_SECRET@"
+          home_domain="${cfg.auth.homeDomain}"
+          ${optionalString (cfg.auth.webAuthDomain != null) ''web_auth_domain="${cfg.auth.webAuthDomain}"''}
+          session_hours=${toString cfg.auth.sessionHours}
+          signers_only=${boolToString cfg.auth.signersOnly}
         '';
         description = ''
-          Configuration file.
+          Configuration file. Secrets are substituted on service start, the
+          cookie key comes from `secretKeyFile` and `@AUTH_SECRET@` is
+          replaced with the contents of `auth.secretFile`.
         '';
       };
     };
@@ -86,6 +181,9 @@ in {
     environment.etc."mtl-users.json" = {
       text = cfg.users;
     };
+    environment.etc."mtl-accounts.json" = {
+      text = cfg.accounts;
+    };
     # Create systemd service
     systemd.services.mtl-multisig = {
       enable = true;
@@ -93,13 +191,21 @@ in {
       after = ["network.target"];
       wants = ["network.target"];
       script = ''
-        ROCKET_CONFIG=/etc/mtl-multisig.toml ${cfg.package}/bin/multisig-service
+        CONFIG=/run/mtl-multisig/config.toml
+        cp /etc/mtl-multisig.toml $CONFIG
+        ${optionalString cfg.auth.enable ''
+          ${pkgs.gnused}/bin/sed -i "s|@AUTH_SECRET@|$(cat ${cfg.auth.secretFile})|" $CONFIG
+        ''}
+        export ROCKET_SECRET_KEY=$(cat ${cfg.secretKeyFile})
+        ROCKET_CONFIG=$CONFIG ${cfg.package}/bin/multisig-service
       '';
       serviceConfig = {
           Restart = "always";
           RestartSec = 30;
           User = "root";
           LimitNOFILE = 65536;
+          RuntimeDirectory = "mtl-multisig";
+          RuntimeDirectoryMode = "0700";
         };
       wantedBy = ["multi-user.target"];
     };
@@ -110,6 +216,9 @@ in {
           if [ ! -d "${cfg.statePath}" ]; then
             mkdir -p ${cfg.statePath}
           fi
+          if [ ! -f "${cfg.secretKeyFile}" ]; then
+            (umask 077; ${pkgs.openssl}/bin/openssl rand -base64 32 > ${cfg.secretKeyFile})
+          fi
         '';
         deps = [];
       };